@@ -0,0 +1,39 @@
+// Times summing the chunks of a 3000x2000 RGB image in 1500x1000 chunks one
+// pixel at a time and through `ChannelSum`, which uses SIMD with the `simd`
+// feature. Compare
+//
+//     cargo bench --bench rowsum
+//     cargo bench --bench rowsum --features simd
+
+#![allow(clippy::needless_return)]
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use tcolr::rowsum::{sum_scalar, ChannelSum};
+
+const WIDTH: usize = 3000;
+const HEIGHT: usize = 2000;
+const CHUNK_WIDTH: usize = 1500;
+const ITERATIONS: u32 = 50;
+
+// Mean time of summing every chunk row of `image` with `sum`.
+fn time(image: &[u8], sum: impl Fn(&[u8], usize) -> [u64; 3]) -> Duration {
+    let start = Instant::now();
+    for _ in 0 .. ITERATIONS {
+        let mut total = [0u64; 3];
+        for run in image.chunks(CHUNK_WIDTH * 3) {
+            let s = sum(black_box(run), 3);
+            total = [total[0] + s[0], total[1] + s[1], total[2] + s[2]];
+        }
+        black_box(total);
+    }
+    return start.elapsed() / ITERATIONS;
+}
+
+fn main() {
+    let mut image = vec![0u8; WIDTH * HEIGHT * 3];
+    fastrand::Rng::with_seed(0).fill(&mut image);
+    let simd = if cfg!(feature = "simd") { "simd" } else { "scalar, without the simd feature" };
+    println!("{:<40}{:>8.2} ms", "pixel by pixel", time(&image, sum_scalar::<u8>).as_secs_f64() * 1000.0);
+    println!("{:<40}{:>8.2} ms", format!("ChannelSum ({})", simd), time(&image, u8::sum_channels).as_secs_f64() * 1000.0);
+}
@@ -0,0 +1,38 @@
+#ifndef TCOLR_H
+#define TCOLR_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ Renders the image file at `path` to fit `cols` x `rows` terminal cells
+ and stores the lines of ANSI text in `*out_buf`, to be released with
+ `tcolr_free`. Unlike the command line tool, `path` must name an existing
+ local file: URLs, glob patterns and `-` for standard input are rejected.
+ An axis given as 0 is unlimited, and with both 0 the image is sampled in
+ the default chunks. Returns 0 on success, and otherwise the exit code of
+ the command line tool with the error message in `*out_buf`, 5 if
+ rendering panicked.
+
+ # Safety
+
+ `path` must be a NUL terminated string and `out_buf` must point to
+ writable memory for a pointer.
+ */
+int tcolr_render_file(const char *path, unsigned int cols, unsigned int rows, char **out_buf);
+
+/*
+ Releases a buffer returned by `tcolr_render_file`.
+
+ # Safety
+
+ `buf` must be null or a buffer returned by `tcolr_render_file` that was
+ not released before.
+ */
+void tcolr_free(char *buf);
+
+#endif  /* TCOLR_H */
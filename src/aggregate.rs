@@ -0,0 +1,258 @@
+use crate::perceptual;
+use crate::{Aggregator, Div, IsSame, ToColour};
+use ansi_term::Colour;
+use clap::ValueEnum;
+use image::{Rgb, Rgba};
+use std::collections::HashMap;
+
+/// Converts an 8-bit sRGB component to linear light in `[0, 1]`.
+pub(crate) fn srgb_to_linear(c: u8) -> f64 {
+    return srgb_unit_to_linear(c as f64 / 255.0);
+}
+
+/// Converts an sRGB component in `[0, 1]` to linear light.
+pub(crate) fn srgb_unit_to_linear(c: f64) -> f64 {
+    return if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) };
+}
+
+/// Converts linear light in `[0, 1]` to an 8-bit sRGB component.
+pub(crate) fn linear_to_srgb(c: f64) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let s = if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+    return (s * 255.0).round() as u8;
+}
+
+/// Averages colours in linear light instead of on the gamma encoded sRGB
+/// values, which keeps fine bright detail from darkening the average.
+#[derive(Clone)]
+pub struct LinearRGBSum {
+    r: f64,
+    g: f64,
+    b: f64,
+}
+
+impl LinearRGBSum {
+    pub fn zero() -> LinearRGBSum {
+        LinearRGBSum { r: 0.0, g: 0.0, b: 0.0 }
+    }
+
+    fn add_srgb(&mut self, r: u64, g: u64, b: u64) {
+        self.r += srgb_to_linear(r.min(255) as u8);
+        self.g += srgb_to_linear(g.min(255) as u8);
+        self.b += srgb_to_linear(b.min(255) as u8);
+    }
+}
+
+impl Div<u64> for LinearRGBSum {
+    fn div_inplace(&mut self, divisor: u64) {
+        self.r /= divisor as f64;
+        self.g /= divisor as f64;
+        self.b /= divisor as f64;
+    }
+}
+
+impl ToColour for LinearRGBSum {
+    fn to_colour(&self) -> Colour {
+        return Colour::RGB(linear_to_srgb(self.r), linear_to_srgb(self.g), linear_to_srgb(self.b));
+    }
+}
+
+impl IsSame for LinearRGBSum {
+    fn is_same(&self, color: &Colour) -> bool {
+        return self.to_colour() == *color;
+    }
+}
+
+impl <U: Into<u64> + Copy> Aggregator<Rgb<U>> for LinearRGBSum {
+    fn aggregate(&mut self, p: &Rgb<U>) {
+        self.add_srgb(p[0].into(), p[1].into(), p[2].into());
+    }
+}
+
+impl <U: Into<u64> + Copy> Aggregator<Rgba<U>> for LinearRGBSum {
+    fn aggregate(&mut self, p: &Rgba<U>) {
+        self.add_srgb(p[0].into(), p[1].into(), p[2].into());
+    }
+}
+
+/// Averages colours in OKLab, which keeps chunks mixing high contrast
+/// colours from turning into the muddy grays of the other averages.
+#[derive(Clone)]
+pub struct OklabSum {
+    l: f64,
+    a: f64,
+    b: f64,
+}
+
+impl OklabSum {
+    pub fn zero() -> OklabSum {
+        OklabSum { l: 0.0, a: 0.0, b: 0.0 }
+    }
+
+    fn add_srgb(&mut self, r: u64, g: u64, b: u64) {
+        let [l, a, b] = perceptual::oklab_from_linear([r, g, b].map(|c| srgb_to_linear(c.min(255) as u8)));
+        self.l += l;
+        self.a += a;
+        self.b += b;
+    }
+}
+
+impl Div<u64> for OklabSum {
+    fn div_inplace(&mut self, divisor: u64) {
+        self.l /= divisor as f64;
+        self.a /= divisor as f64;
+        self.b /= divisor as f64;
+    }
+}
+
+impl ToColour for OklabSum {
+    fn to_colour(&self) -> Colour {
+        let [r, g, b] = perceptual::linear_from_oklab([self.l, self.a, self.b]).map(linear_to_srgb);
+        return Colour::RGB(r, g, b);
+    }
+}
+
+impl IsSame for OklabSum {
+    fn is_same(&self, color: &Colour) -> bool {
+        return self.to_colour() == *color;
+    }
+}
+
+impl <U: Into<u64> + Copy> Aggregator<Rgb<U>> for OklabSum {
+    fn aggregate(&mut self, p: &Rgb<U>) {
+        self.add_srgb(p[0].into(), p[1].into(), p[2].into());
+    }
+}
+
+impl <U: Into<u64> + Copy> Aggregator<Rgba<U>> for OklabSum {
+    fn aggregate(&mut self, p: &Rgba<U>) {
+        self.add_srgb(p[0].into(), p[1].into(), p[2].into());
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum AverageSpace {
+    /// The gamma encoded sRGB values
+    Srgb,
+    /// Linear light, which keeps fine bright detail from darkening the average
+    Linear,
+    /// OKLab, which keeps high contrast chunks from averaging to muddy grays
+    Oklab,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Aggregate {
+    /// Average colour
+    Mean,
+    /// Per channel median
+    Median,
+    /// Most frequent colour
+    Mode,
+    /// Per channel maximum
+    Max,
+    /// Per channel minimum
+    Min,
+}
+
+/// Collects all colours of a chunk and reduces them with an order statistic
+/// once the chunk is complete, i.e. on division.
+#[derive(Clone)]
+pub struct Collected {
+    aggregate: Aggregate,
+    values: Vec<[u8; 3]>,
+    result: [u8; 3],
+}
+
+impl Collected {
+    pub fn new(aggregate: Aggregate) -> Collected {
+        Collected { aggregate, values: Vec::new(), result: [0, 0, 0] }
+    }
+
+    fn push(&mut self, r: u64, g: u64, b: u64) {
+        self.values.push([r.min(255) as u8, g.min(255) as u8, b.min(255) as u8]);
+    }
+
+    fn channel<F: Fn(&mut dyn Iterator<Item = u8>) -> Option<u8>>(&self, f: F) -> [u8; 3] {
+        return [0, 1, 2].map(|c| f(&mut self.values.iter().map(|v| v[c])).unwrap_or(0));
+    }
+
+    fn reduce(&self) -> [u8; 3] {
+        return match self.aggregate {
+            Aggregate::Max => self.channel(|it| it.max()),
+            Aggregate::Min => self.channel(|it| it.min()),
+            Aggregate::Median => self.channel(|it| {
+                let mut v: Vec<u8> = it.collect();
+                v.sort_unstable();
+                v.get(v.len() / 2).copied()
+            }),
+            Aggregate::Mean => self.channel(|it| {
+                let (sum, n) = it.fold((0u64, 0u64), |(s, n), c| (s + c as u64, n + 1));
+                sum.checked_div(n).map(|m| m as u8)
+            }),
+            Aggregate::Mode => {
+                let mut counts: HashMap<[u8; 3], usize> = HashMap::new();
+                for v in &self.values {
+                    *counts.entry(*v).or_insert(0) += 1;
+                }
+                // Ties go to the smallest colour to keep the output deterministic.
+                counts.into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+                    .map(|(c, _)| c)
+                    .unwrap_or([0, 0, 0])
+            }
+        }
+    }
+}
+
+impl Div<u64> for Collected {
+    fn div_inplace(&mut self, _divisor: u64) {
+        self.result = self.reduce();
+        self.values = Vec::new();
+    }
+}
+
+impl ToColour for Collected {
+    fn to_colour(&self) -> Colour {
+        return Colour::RGB(self.result[0], self.result[1], self.result[2]);
+    }
+}
+
+impl IsSame for Collected {
+    fn is_same(&self, color: &Colour) -> bool {
+        return self.to_colour() == *color;
+    }
+}
+
+impl <U: Into<u64> + Copy> Aggregator<Rgb<U>> for Collected {
+    fn aggregate(&mut self, p: &Rgb<U>) {
+        self.push(p[0].into(), p[1].into(), p[2].into());
+    }
+}
+
+impl <U: Into<u64> + Copy> Aggregator<Rgba<U>> for Collected {
+    fn aggregate(&mut self, p: &Rgba<U>) {
+        self.push(p[0].into(), p[1].into(), p[2].into());
+    }
+}
+
+/// Tracks whether any pixel of a chunk is at least partially opaque.
+#[derive(Clone)]
+pub struct Coverage {
+    pub opaque: bool,
+}
+
+impl Coverage {
+    pub fn zero() -> Coverage {
+        Coverage { opaque: false }
+    }
+}
+
+impl Div<u64> for Coverage {
+    fn div_inplace(&mut self, _divisor: u64) {}
+}
+
+impl <U: Into<u64> + Copy> Aggregator<Rgba<U>> for Coverage {
+    fn aggregate(&mut self, p: &Rgba<U>) {
+        self.opaque |= p[3].into() > 0;
+    }
+}
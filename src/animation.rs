@@ -0,0 +1,50 @@
+use bytes::Bytes;
+use image::AnimationDecoder;
+use image::Frame;
+use image::ImageError;
+use image::ImageFormat;
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use std::io::Cursor;
+use std::time::Duration;
+
+// Delay used for frames that do not specify one, matching common browsers.
+const DEFAULT_DELAY_MS: u64 = 100;
+
+/// Decodes all frames of an animated GIF, APNG or WebP, or returns `None` if
+/// `bytes` do not hold an animation.
+pub fn decode_frames(bytes: &Bytes) -> Result<Option<Vec<Frame>>, ImageError> {
+    return match image::guess_format(bytes) {
+        Ok(ImageFormat::Gif) => GifDecoder::new(Cursor::new(bytes))?
+            .into_frames()
+            .collect_frames()
+            .map(Some),
+        Ok(ImageFormat::Png) => decode_apng(bytes),
+        Ok(ImageFormat::WebP) => decode_webp(bytes),
+        _ => Ok(None)
+    }
+}
+
+fn decode_apng(bytes: &Bytes) -> Result<Option<Vec<Frame>>, ImageError> {
+    let decoder = PngDecoder::new(Cursor::new(bytes))?;
+    if !decoder.is_apng()? {
+        return Ok(None);
+    }
+    return decoder.apng()?.into_frames().collect_frames().map(Some);
+}
+
+fn decode_webp(bytes: &Bytes) -> Result<Option<Vec<Frame>>, ImageError> {
+    let decoder = WebPDecoder::new(Cursor::new(bytes))?;
+    if !decoder.has_animation() {
+        return Ok(None);
+    }
+    return decoder.into_frames().collect_frames().map(Some);
+}
+
+/// Time `frame` stays on screen.
+pub fn delay(frame: &Frame) -> Duration {
+    let (numer, denom) = frame.delay().numer_denom_ms();
+    let ms = numer.checked_div(denom).unwrap_or(0) as u64;
+    return Duration::from_millis(if ms == 0 { DEFAULT_DELAY_MS } else { ms });
+}
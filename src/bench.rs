@@ -0,0 +1,74 @@
+use crate::{emit, render_image, Args};
+use crate::error::Error;
+use crate::input::{self, DecodeOptions};
+use crate::timing::{self, Stage};
+use bytes::Bytes;
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use image::imageops::FilterType;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+/// Parses a size given as `WIDTHxHEIGHT`, like `1920x1080`.
+pub fn parse_size(s: &str) -> Result<(u32, u32), String> {
+    return match s.trim().split_once('x').map(|(w, h)| (w.parse::<u32>(), h.parse::<u32>())) {
+        Some((Ok(w), Ok(h))) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(format!("Expected a size like 1920x1080: {}", s))
+    }
+}
+
+// Gradients overlaid with a fine pattern, so that the image neither
+// compresses to nothing nor is sampled into runs of a single colour.
+fn synthetic(width: u32, height: u32) -> DynamicImage {
+    return DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+        let detail = ((x * 7 + y * 13) % 32) as u8;
+        Rgb([
+            (x * 223 / width) as u8 + detail,
+            (y * 223 / height) as u8 + detail,
+            ((x + y) * 223 / (width + height)) as u8 + detail,
+        ])
+    }));
+}
+
+fn milliseconds(total: Duration, iterations: u32) -> String {
+    return format!("{:>14.3}", total.as_secs_f64() * 1000.0 / iterations as f64);
+}
+
+/// Mean milliseconds per iteration spent decoding, aggregating, rendering and
+/// printing `source`, or a synthetic image, resized to each of `sizes` and
+/// encoded as PNG, for each of the chunk sizes in `chunks`. Everything else
+/// is rendered as configured by `args`, and printing writes to nowhere.
+pub fn run(source: Option<&DynamicImage>, sizes: &[(u32, u32)], chunks: &[(u32, u32)], iterations: u32, options: &DecodeOptions, args: &Args) -> Result<Vec<String>, Error> {
+    let mut lines = vec![format!("{:<12}{:<8}{:>14}{:>14}{:>14}{:>14}", "size", "chunks", "decode ms", "aggregate ms", "render ms", "print ms")];
+    for &(width, height) in sizes {
+        let image = match source {
+            Some(image) => image.resize_exact(width, height, FilterType::Triangle),
+            None => synthetic(width, height)
+        };
+        let mut png = Vec::new();
+        image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|e| Error::Render(format!("Unable to encode the benchmark image: {}", e)))?;
+        let png = Bytes::from(png);
+        for &(x_chunks, y_chunks) in chunks {
+            let configured = Args { x_chunks: x_chunks as usize, y_chunks: y_chunks as usize, fit: None, pixel_perfect: false, ..args.clone() };
+            let (aggregated, rendered) = (timing::total(Stage::Aggregate), timing::total(Stage::Render));
+            let (mut decode, mut print) = (Duration::ZERO, Duration::ZERO);
+            for _ in 0 .. iterations {
+                let start = Instant::now();
+                let decoded = input::get_image_from_bytes(png.clone(), "benchmark.png", options)?;
+                decode += start.elapsed();
+                let lines = render_image(&decoded, &configured)?;
+                let start = Instant::now();
+                emit(std::io::sink(), "", &lines, b"\n", true).map_err(|e| Error::Render(e.to_string()))?;
+                print += start.elapsed();
+            }
+            lines.push(format!("{:<12}{:<8}{}{}{}{}",
+                format!("{}x{}", width, height),
+                format!("{}x{}", x_chunks, y_chunks),
+                milliseconds(decode, iterations),
+                milliseconds(timing::total(Stage::Aggregate) - aggregated, iterations),
+                milliseconds(timing::total(Stage::Render) - rendered, iterations),
+                milliseconds(print, iterations)));
+        }
+    }
+    return Ok(lines);
+}
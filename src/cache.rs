@@ -0,0 +1,73 @@
+use bytes::Bytes;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// `$XDG_CACHE_HOME/tcolr`, or `~/.cache/tcolr` if that is not set.
+fn cache_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".cache")
+    };
+    return Some(base.join("tcolr"));
+}
+
+// 64-bit FNV-1a, stable across builds unlike the std hashers.
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in data {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    return hash;
+}
+
+fn path(url: &str) -> Option<PathBuf> {
+    return cache_dir().map(|d| d.join(format!("{:016x}", fnv1a(url.as_bytes()))));
+}
+
+// Cache file of `url`, if it was stored less than `ttl` ago.
+fn fresh_path(url: &str, ttl: Duration) -> Option<PathBuf> {
+    let path = path(url)?;
+    let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+    let age = SystemTime::now().duration_since(modified).unwrap_or_default();
+    return if age > ttl { None } else { Some(path) };
+}
+
+/// Whether a body was cached for `url` less than `ttl` ago.
+pub fn contains(url: &str, ttl: Duration) -> bool {
+    return fresh_path(url, ttl).is_some();
+}
+
+// Media types are kept in a file next to the body, with this extension.
+const MEDIA_TYPE_EXTENSION: &str = "type";
+
+/// Body cached for `url` and the media type it was served with, if it was
+/// stored less than `ttl` ago.
+pub fn load(url: &str, ttl: Duration) -> Option<(Bytes, Option<String>)> {
+    let path = fresh_path(url, ttl)?;
+    let body = std::fs::read(&path).ok().map(Bytes::from)?;
+    let media_type = std::fs::read_to_string(path.with_extension(MEDIA_TYPE_EXTENSION)).ok();
+    return Some((body, media_type));
+}
+
+/// Stores the body of `url` with its media type. Failing to write the cache
+/// is not an error, the next run simply downloads again.
+pub fn store(url: &str, body: &Bytes, media_type: Option<&str>) {
+    if let Some(path) = path(url) {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        // The media type is written first, so that a body is never read with
+        // the type of the one it replaces.
+        let type_path = path.with_extension(MEDIA_TYPE_EXTENSION);
+        let typed = match media_type {
+            Some(t) => std::fs::write(&type_path, t).is_ok(),
+            None => std::fs::remove_file(&type_path).is_ok() || !type_path.exists()
+        };
+        // Written aside and renamed so that concurrent runs never read a partial body.
+        let partial = path.with_extension("part");
+        if typed && std::fs::write(&partial, body).is_ok() {
+            let _ = std::fs::rename(&partial, &path);
+        }
+    }
+}
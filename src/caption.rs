@@ -0,0 +1,39 @@
+use crate::input;
+use crate::render::visible_width;
+
+/// Caption text for `--caption`, where `auto` stands for the file name.
+pub const AUTO: &str = "auto";
+
+/// File name of `uri`, the last segment of its path.
+pub fn file_name(uri: &str) -> String {
+    let path = uri.split(['?', '#']).next().unwrap_or(uri);
+    let name = path.trim_end_matches('/').rsplit('/').next().unwrap_or(path);
+    return match name {
+        "" => uri.to_owned(),
+        name => String::from_utf8_lossy(&input::percent_decode(name)).into_owned()
+    };
+}
+
+/// Size in bytes with a decimal unit, like `2.3MB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{}B", bytes);
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 999.95 && unit + 1 < UNITS.len() {
+        size /= 1000.0;
+        unit += 1;
+    }
+    return format!("{:.1}{}", size, UNITS[unit]);
+}
+
+/// Appends `text` below `lines`, centered under them unless the width of the
+/// lines is unknown, as for the graphics protocols.
+pub fn below(mut lines: Vec<String>, text: &str, centered: bool) -> Vec<String> {
+    let block = if centered { lines.iter().map(|l| visible_width(l)).max().unwrap_or(0) } else { 0 };
+    let indent = block.saturating_sub(text.chars().count()) / 2;
+    lines.push(format!("{}{}", " ".repeat(indent), text));
+    return lines;
+}
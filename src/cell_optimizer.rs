@@ -0,0 +1,185 @@
+use crate::Grid;
+use crate::render::rgb_components;
+use clap::ValueEnum;
+use ansi_term::Colour;
+
+/// Chunks covered by a cell horizontally and vertically, enough for the
+/// halves and quadrants, 3 chunks high, and the sextants, 2 chunks high.
+pub const CELL: (usize, usize) = (2, 6);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Glyphs {
+    /// `▀▄▌▐` and the full block
+    Halves,
+    /// The halves and the quadrant blocks like `▚` and `▙`
+    Quadrants,
+    /// The quadrants and the sextant blocks like `🬗`, which need a recent font
+    Sextants,
+}
+
+// Bit of the chunk in `column` and `row` of a cell.
+fn bit(column: usize, row: usize) -> u16 {
+    return 1 << (row * CELL.0 + column);
+}
+
+// Chunks of the blocks in a grid of `columns` x `rows` blocks per cell,
+// with the blocks numbered row by row as the bits of `blocks`.
+fn mask(blocks: u16, columns: usize, rows: usize) -> u16 {
+    let (width, height) = (CELL.0 / columns, CELL.1 / rows);
+    let mut mask = 0;
+    for y in 0 .. CELL.1 {
+        for x in 0 .. CELL.0 {
+            if blocks & 1 << ((y / height) * columns + x / width) != 0 {
+                mask |= bit(x, y);
+            }
+        }
+    }
+    return mask;
+}
+
+// Quadrant blocks by their quadrants, upper left, upper right, lower left
+// and lower right from the lowest bit up.
+const QUADRANTS: [(u16, char); 14] = [
+    (0b0001, '▘'), (0b0010, '▝'), (0b0011, '▀'), (0b0100, '▖'), (0b0101, '▌'), (0b0110, '▞'), (0b0111, '▛'),
+    (0b1000, '▗'), (0b1001, '▚'), (0b1010, '▐'), (0b1011, '▜'), (0b1100, '▄'), (0b1101, '▙'), (0b1110, '▟'),
+];
+
+// Sextant blocks follow the order of their six bits, upper left to lower
+// right, leaving out the empty and full cell and the left and right halves.
+fn sextant(blocks: u16) -> char {
+    let skipped = 1 + (blocks > 0b010101) as u32 + (blocks > 0b101010) as u32;
+    return char::from_u32(0x1FB00 + blocks as u32 - skipped).unwrap_or(' ');
+}
+
+/// Glyphs of `set` with the chunks they cover in the foreground colour.
+pub fn glyphs(set: Glyphs) -> Vec<(char, u16)> {
+    let mut glyphs = vec![('█', mask(1, 1, 1))];
+    for (blocks, glyph) in QUADRANTS {
+        let half = matches!(glyph, '▀' | '▄' | '▌' | '▐');
+        if half || set >= Glyphs::Quadrants {
+            glyphs.push((glyph, mask(blocks, 2, 2)));
+        }
+    }
+    if set >= Glyphs::Sextants {
+        glyphs.extend((1 .. 0b111111)
+            .filter(|b| *b != 0b010101 && *b != 0b101010)
+            .map(|b| (sextant(b), mask(b, 2, 3))));
+    }
+    return glyphs;
+}
+
+fn mean(colours: &[[f64; 3]]) -> [f64; 3] {
+    let n = colours.len().max(1) as f64;
+    return colours.iter().fold([0.0; 3], |sum, c| [sum[0] + c[0], sum[1] + c[1], sum[2] + c[2]]).map(|c| c / n);
+}
+
+fn squared_error(colours: &[[f64; 3]], to: [f64; 3]) -> f64 {
+    return colours.iter().map(|c| (0 .. 3).map(|i| (c[i] - to[i]).powi(2)).sum::<f64>()).sum();
+}
+
+fn to_colour(c: [f64; 3]) -> Colour {
+    let [r, g, b] = c.map(|c| c.round().clamp(0.0, 255.0) as u8);
+    return Colour::RGB(r, g, b);
+}
+
+/// The glyph of `glyphs` with its foreground and background colour that
+/// draws the chunks of the cell with the least squared error, where
+/// `chunks` holds the chunk colours of the cell row by row and `None` for
+/// chunks beyond the edge of the grid. Each colour is the mean of the chunks
+/// it is drawn over.
+pub fn best(chunks: &[Option<Colour>], glyphs: &[(char, u16)]) -> (char, Colour, Colour) {
+    let present: Vec<(u16, [f64; 3])> = chunks.iter().enumerate()
+        .filter_map(|(i, c)| c.map(|c| (1 << i, rgb_components(&c))))
+        .map(|(bit, (r, g, b))| (bit, [r as f64, g as f64, b as f64]))
+        .collect();
+    let mut best = (f64::INFINITY, ' ', Colour::RGB(0, 0, 0), Colour::RGB(0, 0, 0));
+    for &(glyph, mask) in glyphs {
+        let (fg, bg): (Vec<_>, Vec<_>) = present.iter().partition(|(bit, _)| mask & bit != 0);
+        let fg: Vec<[f64; 3]> = fg.into_iter().map(|(_, c)| c).collect();
+        let bg: Vec<[f64; 3]> = bg.into_iter().map(|(_, c)| c).collect();
+        if fg.is_empty() {
+            continue;
+        }
+        let (fg_mean, bg_mean) = (mean(&fg), mean(&bg));
+        let error = squared_error(&fg, fg_mean) + squared_error(&bg, bg_mean);
+        if error < best.0 {
+            best = (error, glyph, to_colour(fg_mean), to_colour(if bg.is_empty() { fg_mean } else { bg_mean }));
+        }
+    }
+    return (best.1, best.2, best.3);
+}
+
+/// Best glyph and colours of every cell of `CELL` chunks of `grid`, row by row.
+pub fn optimize(grid: &Grid<Colour>, glyphs: &[(char, u16)]) -> Grid<(char, Colour, Colour)> {
+    let (n_x, n_y) = (grid.n_x.div_ceil(CELL.0), grid.n_y.div_ceil(CELL.1));
+    let cells = (0 .. n_y).flat_map(|y| (0 .. n_x).map(move |x| (x, y))).map(|(x_cell, y_cell)| {
+        let chunks: Vec<Option<Colour>> = (0 .. CELL.1)
+            .flat_map(|dy| (0 .. CELL.0).map(move |dx| (x_cell * CELL.0 + dx, y_cell * CELL.1 + dy)))
+            .map(|(x, y)| (x < grid.n_x && y < grid.n_y).then(|| grid.row(y)[x]))
+            .collect();
+        best(&chunks, glyphs)
+    }).collect();
+    return Grid { n_x, n_y, cells };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: Colour = Colour::RGB(0, 0, 0);
+    const WHITE: Colour = Colour::RGB(255, 255, 255);
+
+    // Chunks of a cell painted white where `mask` is set and black elsewhere.
+    fn cell(mask: u16) -> Vec<Option<Colour>> {
+        return (0 .. CELL.0 * CELL.1).map(|i| Some(if mask & 1 << i != 0 { WHITE } else { BLACK })).collect();
+    }
+
+    #[test]
+    fn glyph_sets_grow() {
+        assert_eq!(glyphs(Glyphs::Halves).len(), 5);
+        assert_eq!(glyphs(Glyphs::Quadrants).len(), 15);
+        assert_eq!(glyphs(Glyphs::Sextants).len(), 75);
+    }
+
+    #[test]
+    fn sextants_follow_their_bits() {
+        assert_eq!(sextant(0b000001), '🬀');
+        assert_eq!(sextant(0b010100), '🬓');
+        assert_eq!(sextant(0b010110), '🬔');
+        assert_eq!(sextant(0b111110), '🬻');
+    }
+
+    #[test]
+    fn solid_cells_are_full_blocks() {
+        assert_eq!(best(&cell(0), &glyphs(Glyphs::Sextants)), ('█', BLACK, BLACK));
+    }
+
+    #[test]
+    fn glyphs_are_reproduced_exactly() {
+        // A glyph in inverted colours draws the same as its complement.
+        let set = glyphs(Glyphs::Sextants);
+        for &(_, mask) in &set[1 ..] {
+            let (glyph, fg, bg) = best(&cell(mask), &set);
+            let drawn = set.iter().find(|(g, _)| *g == glyph).map(|(_, m)| *m).unwrap();
+            let colours: Vec<Option<Colour>> = (0 .. CELL.0 * CELL.1).map(|i| Some(if drawn & 1 << i != 0 { fg } else { bg })).collect();
+            assert_eq!(colours, cell(mask));
+        }
+    }
+
+    #[test]
+    fn smaller_sets_approximate() {
+        // The upper left sextant is closest to the upper left quadrant.
+        let chunks = cell(mask(0b000001, 2, 3));
+        assert_eq!(best(&chunks, &glyphs(Glyphs::Quadrants)).0, '▘');
+        assert_eq!(best(&chunks, &glyphs(Glyphs::Halves)).0, '▀');
+    }
+
+    #[test]
+    fn chunks_beyond_the_grid_are_ignored() {
+        let mut chunks = cell(mask(0b0011, 2, 2));
+        for chunk in &mut chunks[6 ..] {
+            *chunk = None;
+        }
+        assert_eq!(best(&chunks, &glyphs(Glyphs::Halves)), ('█', WHITE, WHITE));
+    }
+}
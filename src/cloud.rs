@@ -0,0 +1,158 @@
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use std::time::SystemTime;
+
+/// URL and headers of a GET request for an object in S3 or Google Cloud Storage.
+pub struct Request {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+// Seconds for which a presigned S3 URL is valid.
+const PRESIGN_EXPIRES: u64 = 300;
+
+/// Request for `s3://bucket/key` or `gs://bucket/key`, authenticated with
+/// the credentials the official tools would use, or anonymous for public
+/// objects if there are none.
+pub fn request(uri: &str) -> Result<Request, String> {
+    let (scheme, path) = uri.split_once("://").unwrap_or_default();
+    let (bucket, key) = match path.split_once('/') {
+        Some((bucket, key)) if !bucket.is_empty() && !key.is_empty() => (bucket, key),
+        _ => return Err(format!("Expected {}://bucket/key", scheme))
+    };
+    return match scheme {
+        "s3" => Ok(s3(bucket, key)),
+        _ => Ok(gcs(bucket, key))
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    return std::env::var(name).ok().filter(|v| !v.is_empty());
+}
+
+// Percent encodes everything but the unreserved characters and, in paths, `/`.
+fn encode(s: &str, path: bool) -> String {
+    return s.bytes()
+        .map(|b| match b {
+            b'A' ..= b'Z' | b'a' ..= b'z' | b'0' ..= b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            b'/' if path => String::from("/"),
+            b => format!("%{:02X}", b)
+        })
+        .collect();
+}
+
+fn hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|b| format!("{:02x}", b)).collect();
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    // HMAC with SHA-256 accepts keys of any length and cannot fail.
+    let key = PKey::hmac(key).expect("HMAC keys of any length are valid");
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).expect("SHA-256 is available");
+    return signer.sign_oneshot_to_vec(data.as_bytes()).expect("HMAC signing does not fail");
+}
+
+// Formats seconds since the epoch as `YYYYMMDDTHHMMSSZ`.
+fn amz_date(seconds: u64) -> String {
+    let (days, time) = ((seconds / 86400) as i64, seconds % 86400);
+    // Howard Hinnant's conversion of days since the epoch to a civil date.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    return format!("{:04}{:02}{:02}T{:02}{:02}{:02}Z", year, month, day, time / 3600, time / 60 % 60, time % 60);
+}
+
+struct AwsCredentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+// Credentials from the environment, or the profile of the shared
+// credentials file selected by `$AWS_PROFILE`.
+fn aws_credentials() -> Option<AwsCredentials> {
+    if let (Some(access_key), Some(secret_key)) = (env_var("AWS_ACCESS_KEY_ID"), env_var("AWS_SECRET_ACCESS_KEY")) {
+        return Some(AwsCredentials { access_key, secret_key, session_token: env_var("AWS_SESSION_TOKEN") });
+    }
+    let path = env_var("AWS_SHARED_CREDENTIALS_FILE")
+        .map(std::path::PathBuf::from)
+        .or_else(|| Some(std::path::PathBuf::from(env_var("HOME")?).join(".aws/credentials")))?;
+    let profile = format!("[{}]", env_var("AWS_PROFILE").unwrap_or_else(|| String::from("default")));
+    let contents = std::fs::read_to_string(path).ok()?;
+    let mut values = std::collections::HashMap::new();
+    let mut in_profile = false;
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_profile = line == profile;
+        } else if let (true, Some((name, value))) = (in_profile, line.split_once('=')) {
+            values.insert(name.trim().to_owned(), value.trim().to_owned());
+        }
+    }
+    return Some(AwsCredentials {
+        access_key: values.remove("aws_access_key_id")?,
+        secret_key: values.remove("aws_secret_access_key")?,
+        session_token: values.remove("aws_session_token"),
+    });
+}
+
+// URL of an S3 object, presigned with AWS signature version 4 if there are
+// credentials. `$AWS_ENDPOINT_URL` selects an S3 compatible service, which
+// is addressed with the bucket in the path.
+fn s3(bucket: &str, key: &str) -> Request {
+    let region = env_var("AWS_REGION").or_else(|| env_var("AWS_DEFAULT_REGION")).unwrap_or_else(|| String::from("us-east-1"));
+    let (origin, path) = match env_var("AWS_ENDPOINT_URL_S3").or_else(|| env_var("AWS_ENDPOINT_URL")) {
+        Some(endpoint) => (endpoint.trim_end_matches('/').to_owned(), format!("/{}/{}", encode(bucket, false), encode(key, true))),
+        None => (format!("https://{}.s3.{}.amazonaws.com", bucket, region), format!("/{}", encode(key, true)))
+    };
+    let credentials = match aws_credentials() {
+        Some(c) => c,
+        None => return Request { url: format!("{}{}", origin, path), headers: Vec::new() }
+    };
+    let host = origin.split_once("://").map_or(origin.as_str(), |(_, h)| h);
+    let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs();
+    let timestamp = amz_date(now);
+    let scope = format!("{}/{}/s3/aws4_request", &timestamp[.. 8], region);
+    let mut query = vec![
+        ("X-Amz-Algorithm", String::from("AWS4-HMAC-SHA256")),
+        ("X-Amz-Credential", format!("{}/{}", credentials.access_key, scope)),
+        ("X-Amz-Date", timestamp.clone()),
+        ("X-Amz-Expires", PRESIGN_EXPIRES.to_string()),
+    ];
+    if let Some(token) = &credentials.session_token {
+        query.push(("X-Amz-Security-Token", token.clone()));
+    }
+    query.push(("X-Amz-SignedHeaders", String::from("host")));
+    let query = query.iter().map(|(name, value)| format!("{}={}", name, encode(value, false))).collect::<Vec<_>>().join("&");
+    let canonical = format!("GET\n{}\n{}\nhost:{}\n\nhost\nUNSIGNED-PAYLOAD", path, query, host);
+    let digest = hash(MessageDigest::sha256(), canonical.as_bytes()).expect("SHA-256 is available");
+    let to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", timestamp, scope, hex(&digest));
+    let key = [&timestamp[.. 8], region.as_str(), "s3", "aws4_request"].iter()
+        .fold(format!("AWS4{}", credentials.secret_key).into_bytes(), |key, part| hmac(&key, part));
+    let signature = hex(&hmac(&key, &to_sign));
+    return Request { url: format!("{}{}?{}&X-Amz-Signature={}", origin, path, query, signature), headers: Vec::new() };
+}
+
+// Request for a Cloud Storage object with an OAuth token from
+// `$GOOGLE_OAUTH_ACCESS_TOKEN` or the gcloud CLI, if either is available.
+fn gcs(bucket: &str, key: &str) -> Request {
+    let token = env_var("GOOGLE_OAUTH_ACCESS_TOKEN").or_else(|| {
+        let output = std::process::Command::new("gcloud")
+            .args(["auth", "print-access-token"])
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()?;
+        let token = String::from_utf8(output.stdout).ok()?.trim().to_owned();
+        Some(token).filter(|t| output.status.success() && !t.is_empty())
+    });
+    return Request {
+        url: format!("https://storage.googleapis.com/{}/{}", encode(bucket, false), encode(key, true)),
+        headers: token.map(|t| vec![(String::from("Authorization"), format!("Bearer {}", t))]).unwrap_or_default(),
+    };
+}
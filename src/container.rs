@@ -0,0 +1,65 @@
+// Selection of one image from files holding several, where the image crate
+// always decodes the same one: the first page of a TIFF and the largest
+// size of an icon.
+use crate::error::BoxError;
+use image::{DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Luma, LumaA, Rgb, Rgba, RgbImage, RgbaImage};
+use std::io::Cursor;
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::ColorType;
+
+/// Decodes the page with the zero-based `index` of a multi-page TIFF,
+/// rejecting it before decoding if it has more than `max_pixels`.
+pub fn tiff_page(data: &[u8], index: usize, max_pixels: Option<u64>) -> Result<DynamicImage, BoxError> {
+    let mut decoder = Decoder::new(Cursor::new(data))?;
+    decoder.seek_to_image(index).map_err(|e| format!("Page {} not found: {}", index + 1, e))?;
+    let (width, height) = decoder.dimensions()?;
+    if let Some(max) = max_pixels.filter(|max| width as u64 * height as u64 > *max) {
+        return Err(format!("The page has {}x{} pixels, more than the limit of {} set by --max-pixels", width, height, max).into());
+    }
+    let colour = decoder.colortype()?;
+    let unsupported = || format!("Unsupported colour type {:?} of page {}", colour, index + 1);
+    let image = match (colour, decoder.read_image()?) {
+        (ColorType::Gray(8), DecodingResult::U8(v)) => GrayImage::from_raw(width, height, v).map(DynamicImage::ImageLuma8),
+        (ColorType::GrayA(8), DecodingResult::U8(v)) => GrayAlphaImage::from_raw(width, height, v).map(DynamicImage::ImageLumaA8),
+        (ColorType::RGB(8), DecodingResult::U8(v)) => RgbImage::from_raw(width, height, v).map(DynamicImage::ImageRgb8),
+        (ColorType::RGBA(8), DecodingResult::U8(v)) => RgbaImage::from_raw(width, height, v).map(DynamicImage::ImageRgba8),
+        (ColorType::Gray(16), DecodingResult::U16(v)) => ImageBuffer::<Luma<u16>, _>::from_raw(width, height, v).map(DynamicImage::ImageLuma16),
+        (ColorType::GrayA(16), DecodingResult::U16(v)) => ImageBuffer::<LumaA<u16>, _>::from_raw(width, height, v).map(DynamicImage::ImageLumaA16),
+        (ColorType::RGB(16), DecodingResult::U16(v)) => ImageBuffer::<Rgb<u16>, _>::from_raw(width, height, v).map(DynamicImage::ImageRgb16),
+        (ColorType::RGBA(16), DecodingResult::U16(v)) => ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, v).map(DynamicImage::ImageRgba16),
+        (ColorType::RGB(32), DecodingResult::F32(v)) => ImageBuffer::<Rgb<f32>, _>::from_raw(width, height, v).map(DynamicImage::ImageRgb32F),
+        (ColorType::RGBA(32), DecodingResult::F32(v)) => ImageBuffer::<Rgba<f32>, _>::from_raw(width, height, v).map(DynamicImage::ImageRgba32F),
+        _ => return Err(unsupported().into())
+    };
+    return image.ok_or_else(|| unsupported().into());
+}
+
+// Sizes of the ICONDIR header and of each ICONDIRENTRY of an icon file.
+const ICO_HEADER: usize = 6;
+const ICO_ENTRY: usize = 16;
+
+/// Copy of an icon file reduced to the entry whose width is closest to
+/// `size`, preferring larger ones, so that the decoder cannot pick another.
+/// `None` for icons with a single entry and malformed ones.
+pub fn ico_entry(data: &[u8], size: u32) -> Option<Vec<u8>> {
+    let count = u16::from_le_bytes([*data.get(4)?, *data.get(5)?]) as usize;
+    if count < 2 {
+        return None;
+    }
+    let entries: Vec<&[u8]> = (0 .. count)
+        .map(|i| data.get(ICO_HEADER + i * ICO_ENTRY .. ICO_HEADER + (i + 1) * ICO_ENTRY))
+        .collect::<Option<_>>()?;
+    // A width of 0 stands for 256 pixels.
+    let width = |entry: &[u8]| if entry[0] == 0 { 256 } else { entry[0] as u32 };
+    let entry = entries.iter().min_by_key(|e| (width(e).abs_diff(size), std::cmp::Reverse(width(e))))?;
+    let length = u32::from_le_bytes(entry[8 .. 12].try_into().ok()?) as usize;
+    let offset = u32::from_le_bytes(entry[12 .. 16].try_into().ok()?) as usize;
+    let image = data.get(offset .. offset.checked_add(length)?)?;
+    let mut single = Vec::with_capacity(ICO_HEADER + ICO_ENTRY + length);
+    single.extend_from_slice(&data[.. 4]);
+    single.extend_from_slice(&1u16.to_le_bytes());
+    single.extend_from_slice(&entry[.. 12]);
+    single.extend_from_slice(&((ICO_HEADER + ICO_ENTRY) as u32).to_le_bytes());
+    single.extend_from_slice(image);
+    return Some(single);
+}
@@ -0,0 +1,141 @@
+use image::DynamicImage;
+
+/// Offset or extent along one axis of the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Length {
+    Pixels(u32),
+    Percent(f64),
+}
+
+impl Length {
+    fn parse(s: &str) -> Result<Length, String> {
+        let s = s.trim();
+        return match s.strip_suffix('%') {
+            Some(p) => match p.trim().parse::<f64>() {
+                Ok(v) if (0.0 ..= 100.0).contains(&v) => Ok(Length::Percent(v)),
+                _ => Err(format!("Expected a percentage between 0% and 100%: {}", s))
+            },
+            None => s.parse::<u32>()
+                .map(Length::Pixels)
+                .map_err(|_| format!("Expected a number of pixels or a percentage: {}", s))
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        return matches!(self, Length::Pixels(0)) || matches!(self, Length::Percent(p) if *p == 0.0);
+    }
+
+    fn resolve(&self, size: u32) -> u32 {
+        return match self {
+            Length::Pixels(p) => *p,
+            Length::Percent(p) => (size as f64 * p / 100.0).round() as u32
+        }
+    }
+}
+
+/// Region of an image given as `x,y,w,h`, each in pixels or as a percentage
+/// of the image width or height.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crop {
+    x: Length,
+    y: Length,
+    width: Length,
+    height: Length,
+}
+
+pub fn parse(s: &str) -> Result<Crop, String> {
+    let parts: Vec<&str> = s.split(',').collect();
+    if parts.len() != 4 {
+        return Err(format!("Expected x,y,w,h: {}", s));
+    }
+    let crop = Crop {
+        x: Length::parse(parts[0])?,
+        y: Length::parse(parts[1])?,
+        width: Length::parse(parts[2])?,
+        height: Length::parse(parts[3])?,
+    };
+    if crop.width.is_zero() || crop.height.is_zero() {
+        return Err(format!("Expected a crop region wider and taller than 0: {}", s));
+    }
+    return Ok(crop);
+}
+
+impl Crop {
+    /// The part of `image` covered by the region, clipped to the image bounds.
+    pub fn apply(&self, image: &DynamicImage) -> Result<DynamicImage, String> {
+        let (w, h) = (image.width(), image.height());
+        let x = self.x.resolve(w);
+        let y = self.y.resolve(h);
+        let width = self.width.resolve(w).min(w.saturating_sub(x));
+        let height = self.height.resolve(h).min(h.saturating_sub(y));
+        if x >= w || y >= h {
+            return Err(format!("The crop region lies outside of the {}x{} image", w, h));
+        }
+        if width == 0 || height == 0 {
+            return Err(format!("The crop region covers less than a pixel of the {}x{} image", w, h));
+        }
+        return Ok(image.crop_imm(x, y, width, height));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    fn image(width: u32, height: u32) -> DynamicImage {
+        return DynamicImage::ImageRgb8(RgbImage::new(width, height));
+    }
+
+    fn size(crop: &str, image: &DynamicImage) -> Result<(u32, u32), String> {
+        return parse(crop)?.apply(image).map(|i| (i.width(), i.height()));
+    }
+
+    #[test]
+    fn parses_pixels_and_percentages() {
+        let crop = parse("10, 20,50%,100 %").unwrap();
+        assert_eq!(crop, Crop { x: Length::Pixels(10), y: Length::Pixels(20), width: Length::Percent(50.0), height: Length::Percent(100.0) });
+    }
+
+    #[test]
+    fn rejects_malformed_regions() {
+        assert!(parse("1,2,3").is_err());
+        assert!(parse("1,2,3,4,5").is_err());
+        assert!(parse("-1,0,10,10").is_err());
+        assert!(parse("0,0,101%,10").is_err());
+        assert!(parse("0,0,ten,10").is_err());
+    }
+
+    #[test]
+    fn crops_absolute_regions() {
+        let cropped = parse("10,20,30,40").unwrap().apply(&image(100, 80)).unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (30, 40));
+    }
+
+    #[test]
+    fn crops_percentages_of_the_image_size() {
+        assert_eq!(size("25%,50%,50%,25%", &image(200, 100)), Ok((100, 25)));
+        assert_eq!(size("0,0,100%,100%", &image(7, 3)), Ok((7, 3)));
+    }
+
+    #[test]
+    fn clips_regions_to_the_image() {
+        assert_eq!(size("90,70,50,50", &image(100, 80)), Ok((10, 10)));
+        assert_eq!(size("50%,50%,100%,100%", &image(100, 80)), Ok((50, 40)));
+    }
+
+    #[test]
+    fn rejects_regions_outside_of_the_image() {
+        assert!(size("100,0,10,10", &image(100, 80)).is_err());
+        assert!(size("0,80,10,10", &image(100, 80)).is_err());
+        assert!(size("100%,0,10,10", &image(100, 80)).is_err());
+    }
+
+    #[test]
+    fn rejects_zero_sized_regions() {
+        assert!(parse("0,0,0,10").is_err());
+        assert!(parse("0,0,10,0%").is_err());
+        // 1% of 20 pixels rounds to nothing.
+        assert!(size("0,0,1%,10", &image(20, 20)).is_err());
+    }
+}
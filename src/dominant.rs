@@ -0,0 +1,134 @@
+use crate::render::rgb_components;
+use ansi_term::Colour;
+use image::DynamicImage;
+use image::imageops::FilterType;
+use std::collections::HashMap;
+
+// Images are reduced to at most this many pixels per side before clustering.
+const MAX_SIDE: u32 = 256;
+
+/// A colour of the image together with the fraction of pixels it represents.
+pub struct Swatch {
+    pub rgb: (u8, u8, u8),
+    pub share: f64,
+}
+
+// Index and extent of the channel with the largest range in `pixels`.
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    return (0 .. 3)
+        .map(|c| {
+            let min = pixels.iter().map(|p| p[c]).min().unwrap_or(0);
+            let max = pixels.iter().map(|p| p[c]).max().unwrap_or(0);
+            (c, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0));
+}
+
+fn average(pixels: &[[u8; 3]]) -> (u8, u8, u8) {
+    let n = pixels.len().max(1) as u64;
+    let sum = pixels.iter().fold([0u64; 3], |s, p| [s[0] + p[0] as u64, s[1] + p[1] as u64, s[2] + p[2] as u64]);
+    return ((sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8);
+}
+
+// Splits `pixels` into up to `count` boxes by median cut, each time
+// splitting the box with the widest channel range at its median.
+fn median_cut(pixels: Vec<[u8; 3]>, count: usize) -> Vec<Vec<[u8; 3]>> {
+    let mut boxes = vec![pixels];
+    while boxes.len() < count {
+        let (index, channel, range) = boxes.iter()
+            .enumerate()
+            .map(|(i, b)| {
+                let (c, r) = widest_channel(b);
+                (i, c, r)
+            })
+            .max_by_key(|(_, _, r)| *r)
+            .unwrap_or((0, 0, 0));
+        if range == 0 {
+            break;
+        }
+        let mut b = boxes.swap_remove(index);
+        b.sort_unstable_by_key(|p| p[channel]);
+        let upper = b.split_off(b.len() / 2);
+        boxes.push(b);
+        boxes.push(upper);
+    }
+    return boxes;
+}
+
+/// Up to `count` dominant colours of `image` found by median cut, most
+/// frequent first. Fully transparent pixels are ignored.
+pub fn extract(image: &DynamicImage, count: usize) -> Vec<Swatch> {
+    let image = if image.width() > MAX_SIDE || image.height() > MAX_SIDE {
+        image.resize(MAX_SIDE, MAX_SIDE, FilterType::Triangle)
+    } else {
+        image.clone()
+    };
+    let pixels: Vec<[u8; 3]> = image.to_rgba8().pixels()
+        .filter(|p| p[3] > 0)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect();
+    let total = pixels.len();
+    if total == 0 || count == 0 {
+        return Vec::new();
+    }
+
+    let boxes = median_cut(pixels, count);
+    let mut swatches: Vec<Swatch> = boxes.iter()
+        .map(|b| Swatch { rgb: average(b), share: b.len() as f64 / total as f64 })
+        .collect();
+    swatches.sort_by(|a, b| b.share.total_cmp(&a.share));
+    return swatches;
+}
+
+/// Replaces the `colours` by the nearest of at most `count` colours found in
+/// them by median cut.
+pub fn posterize(colours: &mut [Colour], count: usize) {
+    if colours.is_empty() {
+        return;
+    }
+    let pixels: Vec<[u8; 3]> = colours.iter().map(|c| {
+        let (r, g, b) = rgb_components(c);
+        [r, g, b]
+    }).collect();
+    let levels: Vec<[i32; 3]> = median_cut(pixels, count).iter()
+        .map(|b| {
+            let (r, g, b) = average(b);
+            [r as i32, g as i32, b as i32]
+        })
+        .collect();
+    let mut nearest: HashMap<(u8, u8, u8), Colour> = HashMap::new();
+    for colour in colours.iter_mut() {
+        let rgb = rgb_components(colour);
+        *colour = *nearest.entry(rgb).or_insert_with(|| {
+            let c = [rgb.0 as i32, rgb.1 as i32, rgb.2 as i32];
+            let level = levels.iter()
+                .min_by_key(|l| (0 .. 3).map(|i| (l[i] - c[i]).pow(2)).sum::<i32>())
+                .copied()
+                .unwrap_or(c);
+            Colour::RGB(level[0] as u8, level[1] as u8, level[2] as u8)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posterize_limits_the_colours() {
+        let mut colours: Vec<Colour> = (0 ..= 255).map(|v| Colour::RGB(v, 255 - v, 128)).collect();
+        posterize(&mut colours, 4);
+        let distinct: std::collections::HashSet<_> = colours.iter().map(rgb_components).collect();
+        assert_eq!(distinct.len(), 4);
+        assert_eq!(colours[0], Colour::RGB(31, 223, 128));
+    }
+
+    #[test]
+    fn posterize_keeps_fewer_colours() {
+        let mut colours = vec![Colour::RGB(1, 2, 3), Colour::RGB(200, 100, 0), Colour::RGB(1, 2, 3)];
+        let original = colours.clone();
+        posterize(&mut colours, 8);
+        assert_eq!(colours, original);
+    }
+}
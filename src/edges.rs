@@ -0,0 +1,101 @@
+use crate::Grid;
+use crate::palette::{self, ColorDepth};
+use crate::render::{luminance, rgb_components, BRAILLE_DOTS};
+use ansi_term::Colour;
+use clap::ValueEnum;
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Glyphs {
+    /// A braille dot for every chunk on an edge
+    Braille,
+    /// A box drawing line along the edge through each cell
+    Lines,
+}
+
+// Horizontal and vertical Sobel gradients of the chunk luminance, with the
+// chunks at the border repeated outwards. A step from black to white has a
+// gradient of 1.
+fn sobel(grid: &Grid<Colour>) -> Grid<(f64, f64)> {
+    let l = |x: isize, y: isize| {
+        let x = x.clamp(0, grid.n_x as isize - 1) as usize;
+        let y = y.clamp(0, grid.n_y as isize - 1) as usize;
+        luminance(&grid.row(y)[x]) / 255.0
+    };
+    let cells = (0 .. grid.n_y as isize).flat_map(|y| (0 .. grid.n_x as isize).map(move |x| (x, y))).map(|(x, y)| {
+        let gx = (l(x + 1, y - 1) + 2.0 * l(x + 1, y) + l(x + 1, y + 1)) - (l(x - 1, y - 1) + 2.0 * l(x - 1, y) + l(x - 1, y + 1));
+        let gy = (l(x - 1, y + 1) + 2.0 * l(x, y + 1) + l(x + 1, y + 1)) - (l(x - 1, y - 1) + 2.0 * l(x, y - 1) + l(x + 1, y - 1));
+        (gx / 4.0, gy / 4.0)
+    }).collect();
+    return Grid { n_x: grid.n_x, n_y: grid.n_y, cells };
+}
+
+// Line drawing character for an edge across a gradient pointing `angle`
+// degrees clockwise from the x axis, with y pointing down.
+fn line(angle: f64) -> char {
+    return match angle.rem_euclid(180.0) {
+        a if !(22.5 .. 157.5).contains(&a) => '│',
+        a if a < 67.5 => '╱',
+        a if a < 112.5 => '─',
+        _ => '╲'
+    };
+}
+
+/// Edges found in the luminance of `grid` where the gradient is at least
+/// `threshold`, drawn with `glyphs` in cells of 2x4 chunks. Each glyph is
+/// painted with the average colour of its chunks unless `depth` is
+/// `NoColor`, and cells without an edge are left blank.
+pub fn edges(grid: &Grid<Colour>, glyphs: Glyphs, threshold: f64, depth: ColorDepth) -> Vec<String> {
+    let gradients = sobel(grid);
+    let mut lines = Vec::with_capacity(grid.n_y.div_ceil(4));
+    for y_cell in (0 .. grid.n_y).step_by(4) {
+        let mut text = String::new();
+        for x_cell in (0 .. grid.n_x).step_by(2) {
+            let chunks: Vec<(usize, usize)> = (0 .. 4)
+                .flat_map(|dy| (0 .. 2).map(move |dx| (dx, dy)))
+                .filter(|(dx, dy)| x_cell + dx < grid.n_x && y_cell + dy < grid.n_y)
+                .collect();
+            let gradient = |dx: usize, dy: usize| gradients.row(y_cell + dy)[x_cell + dx];
+            let glyph = match glyphs {
+                Glyphs::Braille => {
+                    let bits = chunks.iter()
+                        .filter(|(dx, dy)| f64::hypot(gradient(*dx, *dy).0, gradient(*dx, *dy).1) >= threshold)
+                        .fold(0, |bits, (dx, dy)| bits | BRAILLE_DOTS[*dy][*dx]);
+                    if bits == 0 { ' ' } else { char::from_u32(0x2800 + bits).unwrap_or(' ') }
+                },
+                Glyphs::Lines => {
+                    // The gradients on both sides of a thin line point in
+                    // opposite directions and would cancel out, so their
+                    // orientations are averaged as doubled angles instead.
+                    let (mut cos, mut sin, mut strength) = (0.0, 0.0, 0.0);
+                    for (dx, dy) in &chunks {
+                        let (gx, gy) = gradient(*dx, *dy);
+                        cos += gx * gx - gy * gy;
+                        sin += 2.0 * gx * gy;
+                        strength += f64::hypot(gx, gy);
+                    }
+                    match strength / chunks.len() as f64 {
+                        s if s < threshold => ' ',
+                        _ => line(f64::atan2(sin, cos).to_degrees() / 2.0)
+                    }
+                }
+            };
+            if glyph == ' ' {
+                text.push(' ');
+                continue;
+            }
+            let (mut r, mut g, mut b) = (0u32, 0u32, 0u32);
+            for (dx, dy) in &chunks {
+                let (cr, cg, cb) = rgb_components(&grid.row(y_cell + dy)[x_cell + dx]);
+                r += cr as u32;
+                g += cg as u32;
+                b += cb as u32;
+            }
+            let n = chunks.len() as u32;
+            let style = palette::foreground(Colour::RGB((r / n) as u8, (g / n) as u8, (b / n) as u8), depth);
+            let _ = write!(text, "{}{}{}", style.prefix(), glyph, style.suffix());
+        }
+        lines.push(text);
+    }
+    return lines;
+}
@@ -0,0 +1,111 @@
+use crate::aggregate::{linear_to_srgb, srgb_to_linear};
+use crate::render::{luminance, rgb_components};
+use ansi_term::Colour;
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Effect {
+    /// Luminance as a shade of gray
+    Grayscale,
+    /// Warm brown tones
+    Sepia,
+    /// Complementary colours
+    Invert,
+}
+
+fn clamp(c: f64) -> u8 {
+    return c.round().clamp(0.0, 255.0) as u8;
+}
+
+/// Applies `effect` to an RGB colour.
+pub fn apply(colour: Colour, effect: Effect) -> Colour {
+    let (r, g, b) = rgb_components(&colour);
+    return match effect {
+        Effect::Grayscale => {
+            let l = clamp(luminance(&colour));
+            Colour::RGB(l, l, l)
+        },
+        Effect::Sepia => {
+            let (r, g, b) = (r as f64, g as f64, b as f64);
+            Colour::RGB(
+                clamp(0.393 * r + 0.769 * g + 0.189 * b),
+                clamp(0.349 * r + 0.686 * g + 0.168 * b),
+                clamp(0.272 * r + 0.534 * g + 0.131 * b))
+        },
+        Effect::Invert => Colour::RGB(255 - r, 255 - g, 255 - b)
+    }
+}
+
+/// Factors scaling brightness, contrast and saturation, `1.0` leaves the
+/// colour unchanged.
+#[derive(Clone, Copy, Debug)]
+pub struct Adjustments {
+    pub brightness: f64,
+    pub contrast: f64,
+    pub saturation: f64,
+}
+
+impl Adjustments {
+    fn is_identity(&self) -> bool {
+        return self.brightness == 1.0 && self.contrast == 1.0 && self.saturation == 1.0;
+    }
+}
+
+/// Scales the channels of an RGB colour by the brightness factor, stretches
+/// them away from mid gray by the contrast factor, and moves them away from
+/// the colour's luminance by the saturation factor.
+pub fn adjust(colour: Colour, adjustments: &Adjustments) -> Colour {
+    if adjustments.is_identity() {
+        return colour;
+    }
+    let (r, g, b) = rgb_components(&colour);
+    let channels = [r, g, b].map(|c| {
+        let c = c as f64 * adjustments.brightness;
+        (c - 127.5) * adjustments.contrast + 127.5
+    });
+    let l = 0.2126 * channels[0] + 0.7152 * channels[1] + 0.0722 * channels[2];
+    let [r, g, b] = channels.map(|c| clamp(l + (c - l) * adjustments.saturation));
+    return Colour::RGB(r, g, b);
+}
+
+/// Colour vision deficiency lacking one type of cone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Deficiency {
+    /// No long wavelength (red) cones
+    Protanopia,
+    /// No medium wavelength (green) cones
+    Deuteranopia,
+    /// No short wavelength (blue) cones
+    Tritanopia,
+}
+
+const RGB_TO_LMS: [[f64; 3]; 3] = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+
+const LMS_TO_RGB: [[f64; 3]; 3] = [
+    [0.0809444479, -0.130504409, 0.116721066],
+    [-0.0102485335, 0.0540193266, -0.113614708],
+    [-0.000365296938, -0.00412161469, 0.693511405],
+];
+
+fn multiply(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    return m.map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2]);
+}
+
+/// How an RGB colour appears with `deficiency`, after Viénot, Brettel and
+/// Mollon (1999): the response of the missing cones is reconstructed from
+/// the other two in LMS space, in linear light.
+pub fn simulate(colour: Colour, deficiency: Deficiency) -> Colour {
+    let (r, g, b) = rgb_components(&colour);
+    let [l, m, s] = multiply(&RGB_TO_LMS, [r, g, b].map(srgb_to_linear));
+    let lms = match deficiency {
+        Deficiency::Protanopia => [2.02344 * m - 2.52581 * s, m, s],
+        Deficiency::Deuteranopia => [l, 0.494207 * l + 1.24827 * s, s],
+        Deficiency::Tritanopia => [l, m, -0.395913 * l + 0.801109 * m],
+    };
+    let [r, g, b] = multiply(&LMS_TO_RGB, lms).map(linear_to_srgb);
+    return Colour::RGB(r, g, b);
+}
@@ -0,0 +1,51 @@
+use std::error::Error as StdError;
+
+pub type BoxError = Box<dyn StdError + Send + Sync>;
+
+pub const EXIT_CODES: &str = "\
+Exit codes:
+  0  success
+  1  compared images are less similar than the threshold
+  2  invalid usage
+  3  an input could not be fetched
+  4  an input could not be decoded
+  5  an image could not be rendered";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// Arguments that parse but cannot be honoured for the given inputs.
+    #[error("{0}")]
+    Usage(String),
+    #[error("Unsupported scheme {scheme}:// in {uri}")]
+    UnsupportedScheme { scheme: String, uri: String },
+    #[error("Unable to fetch {uri}: {source}")]
+    Fetch { uri: String, source: BoxError },
+    #[error("Unable to decode {uri}: {source}")]
+    Decode { uri: String, source: BoxError },
+    #[error("{0}")]
+    Render(String),
+    /// Images compared successfully, but found to differ.
+    #[error("{0}")]
+    Mismatch(String),
+}
+
+impl Error {
+    pub fn fetch<E: Into<BoxError>>(uri: &str, source: E) -> Error {
+        return Error::Fetch { uri: uri.to_owned(), source: source.into() };
+    }
+
+    pub fn decode<E: Into<BoxError>>(uri: &str, source: E) -> Error {
+        return Error::Decode { uri: uri.to_owned(), source: source.into() };
+    }
+
+    /// Process exit code reporting this error, as listed in `EXIT_CODES`.
+    pub fn exit_code(&self) -> i32 {
+        return match self {
+            Error::Usage(_) => 2,
+            Error::UnsupportedScheme { .. } | Error::Fetch { .. } => 3,
+            Error::Decode { .. } => 4,
+            Error::Render(_) => 5,
+            Error::Mismatch(_) => 1,
+        }
+    }
+}
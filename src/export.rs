@@ -0,0 +1,269 @@
+use crate::Grid;
+use crate::dominant::Swatch;
+use crate::palette;
+use crate::render::rgb_components;
+use ansi_term::Colour;
+use clap::ValueEnum;
+use image::{Rgb, RgbImage};
+
+fn hex(colour: &Colour) -> String {
+    let (r, g, b) = rgb_components(colour);
+    return format!("#{:02x}{:02x}{:02x}", r, g, b);
+}
+
+/// The grid as a single line JSON document with its dimensions and the RGB
+/// value and hex code of every cell, row by row.
+pub fn json(grid: &Grid<Colour>) -> Vec<String> {
+    let rows: Vec<String> = (0 .. grid.n_y)
+        .map(|y| {
+            let cells: Vec<String> = grid.row(y).iter()
+                .map(|c| {
+                    let (r, g, b) = rgb_components(c);
+                    format!("{{\"rgb\":[{},{},{}],\"hex\":\"{}\"}}", r, g, b, hex(c))
+                })
+                .collect();
+            format!("[{}]", cells.join(","))
+        })
+        .collect();
+    return vec![format!("{{\"width\":{},\"height\":{},\"cells\":[{}]}}", grid.n_x, grid.n_y, rows.join(","))];
+}
+
+/// The grid with a header and one `row,col,r,g,b` record per cell, the
+/// fields separated by `separator`.
+pub fn delimited(grid: &Grid<Colour>, separator: char) -> Vec<String> {
+    let mut out = vec![["row", "col", "r", "g", "b"].join(&separator.to_string())];
+    for y in 0 .. grid.n_y {
+        for (x, c) in grid.row(y).iter().enumerate() {
+            let (r, g, b) = rgb_components(c);
+            out.push([y, x, r as usize, g as usize, b as usize].map(|v| v.to_string()).join(&separator.to_string()));
+        }
+    }
+    return out;
+}
+
+fn css(colour: Colour) -> String {
+    let (r, g, b) = palette::to_rgb(colour);
+    return format!("#{:02x}{:02x}{:02x}", r, g, b);
+}
+
+fn escape_html(c: char, out: &mut String) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        c => out.push(c)
+    }
+}
+
+// Applies the parameters of one SGR escape to the current colours.
+fn apply_sgr(params: &str, fg: &mut Option<Colour>, bg: &mut Option<Colour>) {
+    let codes: Vec<u8> = params.split(';').map(|p| p.parse().unwrap_or(0)).collect();
+    let mut i = 0;
+    while i < codes.len() {
+        let target = match codes[i] {
+            30 ..= 39 | 90 ..= 97 => &mut *fg,
+            40 ..= 49 | 100 ..= 107 => &mut *bg,
+            0 => {
+                *fg = None;
+                *bg = None;
+                i += 1;
+                continue;
+            },
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let code = codes[i];
+        *target = match code % 10 {
+            8 if codes.get(i + 1) == Some(&2) && i + 4 < codes.len() => {
+                i += 4;
+                Some(Colour::RGB(codes[i - 2], codes[i - 1], codes[i]))
+            },
+            8 if codes.get(i + 1) == Some(&5) && i + 2 < codes.len() => {
+                i += 2;
+                Some(Colour::Fixed(codes[i]))
+            },
+            9 => None,
+            n if code >= 90 => Some(Colour::Fixed(8 + n)),
+            n => Some(Colour::Fixed(n))
+        };
+        i += 1;
+    }
+}
+
+/// The rendered `lines` as a `<pre>` block, with the colours of their SGR
+/// escapes reproduced by inline styled spans.
+pub fn html(lines: &[String]) -> Vec<String> {
+    let mut out = vec![String::from("<pre style=\"font-family: monospace; line-height: 1;\">")];
+    for line in lines {
+        let mut html = String::new();
+        let (mut fg, mut bg) = (None, None);
+        let mut chars = line.chars().peekable();
+        let mut open = false;
+        while let Some(c) = chars.next() {
+            if c == '\x1b' && chars.peek() == Some(&'[') {
+                chars.next();
+                let params: String = chars.by_ref().take_while(|c| *c != 'm').collect();
+                apply_sgr(&params, &mut fg, &mut bg);
+                if open {
+                    html.push_str("</span>");
+                    open = false;
+                }
+                continue;
+            }
+            if !open && (fg.is_some() || bg.is_some()) {
+                let mut style = String::new();
+                if let Some(f) = fg {
+                    style.push_str(&format!("color:{};", css(f)));
+                }
+                if let Some(b) = bg {
+                    style.push_str(&format!("background-color:{};", css(b)));
+                }
+                html.push_str(&format!("<span style=\"{}\">", style));
+                open = true;
+            }
+            escape_html(c, &mut html);
+        }
+        if open {
+            html.push_str("</span>");
+        }
+        out.push(html);
+    }
+    out.push(String::from("</pre>"));
+    return out;
+}
+
+/// The grid as an SVG mosaic with one `cell_width` x `cell_height` rectangle
+/// per chunk.
+pub fn svg(grid: &Grid<Colour>, cell_width: usize, cell_height: usize) -> Vec<String> {
+    let (width, height) = (grid.n_x * cell_width, grid.n_y * cell_height);
+    let mut out = vec![format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
+        width, height, width, height)];
+    for y in 0 .. grid.n_y {
+        for (x, c) in grid.row(y).iter().enumerate() {
+            out.push(format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                x * cell_width, y * cell_height, cell_width, cell_height, hex(c)));
+        }
+    }
+    out.push(String::from("</svg>"));
+    return out;
+}
+
+/// The grid in the NumPy `.npy` format, as an array of `n_y` x `n_x` x 3
+/// bytes.
+pub fn npy(grid: &Grid<Colour>) -> Vec<u8> {
+    let mut header = format!("{{'descr': '|u1', 'fortran_order': False, 'shape': ({}, {}, 3), }}", grid.n_y, grid.n_x);
+    // The magic string, version and header length take 10 bytes, and the
+    // data starts aligned to 64 bytes after a newline.
+    let padding = 63 - (10 + header.len()) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+    let mut out = b"\x93NUMPY\x01\x00".to_vec();
+    out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    out.extend_from_slice(header.as_bytes());
+    out.extend_from_slice(&thumbnail(grid).into_raw());
+    return out;
+}
+
+/// The grid as an image with one pixel per chunk.
+pub fn thumbnail(grid: &Grid<Colour>) -> RgbImage {
+    return RgbImage::from_fn(grid.n_x as u32, grid.n_y as u32, |x, y| {
+        let (r, g, b) = rgb_components(&grid.row(y as usize)[x as usize]);
+        Rgb([r, g, b])
+    });
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PaletteFormat {
+    /// Coloured swatches with their hex codes and shares
+    Swatches,
+    /// Shell variables color0 to colorN, like the colors.sh of pywal
+    Sh,
+    /// X resources *.color0 to *.colorN
+    Xresources,
+    /// GIMP palette
+    Gpl,
+    /// CSS custom properties --color0 to --colorN
+    Css,
+    /// JSON document with the hex code, RGB value and share of every colour
+    Json,
+}
+
+/// The `swatches` as a palette file in `format`, other than swatches.
+pub fn palette(swatches: &[Swatch], format: PaletteFormat) -> Vec<String> {
+    let hexes: Vec<String> = swatches.iter()
+        .map(|s| hex(&Colour::RGB(s.rgb.0, s.rgb.1, s.rgb.2)))
+        .collect();
+    return match format {
+        PaletteFormat::Swatches => hexes,
+        PaletteFormat::Sh => hexes.iter().enumerate()
+            .map(|(i, h)| format!("color{}='{}'", i, h))
+            .collect(),
+        PaletteFormat::Xresources => hexes.iter().enumerate()
+            .map(|(i, h)| format!("*.color{}: {}", i, h))
+            .collect(),
+        PaletteFormat::Gpl => {
+            let mut out = vec![String::from("GIMP Palette"), String::from("Name: tcolr"), String::from("#")];
+            out.extend(swatches.iter().zip(&hexes)
+                .map(|(s, h)| format!("{:3} {:3} {:3}\t{}", s.rgb.0, s.rgb.1, s.rgb.2, h)));
+            out
+        },
+        PaletteFormat::Css => {
+            let mut out = vec![String::from(":root {")];
+            out.extend(hexes.iter().enumerate().map(|(i, h)| format!("  --color{}: {};", i, h)));
+            out.push(String::from("}"));
+            out
+        },
+        PaletteFormat::Json => {
+            let colours: Vec<String> = swatches.iter().zip(&hexes)
+                .map(|(s, h)| format!("{{\"hex\":\"{}\",\"rgb\":[{},{},{}],\"share\":{:.4}}}", h, s.rgb.0, s.rgb.1, s.rgb.2, s.share))
+                .collect();
+            vec![format!("{{\"colors\":[{}]}}", colours.join(","))]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Cell `(x, y)` of a `n_x` x `n_y` grid is coloured (x, y, 7).
+    fn grid(n_x: usize, n_y: usize) -> Grid<Colour> {
+        let cells = (0 .. n_y).flat_map(|y| (0 .. n_x).map(move |x| Colour::RGB(x as u8, y as u8, 7))).collect();
+        return Grid::new(n_x, n_y, cells).unwrap();
+    }
+
+    // Header text and data of a version 1.0 `.npy` file.
+    fn split(npy: &[u8]) -> (&str, &[u8]) {
+        assert_eq!(&npy[.. 8], b"\x93NUMPY\x01\x00");
+        let length = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+        return (std::str::from_utf8(&npy[10 .. 10 + length]).unwrap(), &npy[10 + length ..]);
+    }
+
+    #[test]
+    fn npy_header_describes_an_unstrided_array_of_bytes() {
+        let npy = npy(&grid(3, 2));
+        let (header, _) = split(&npy);
+        assert!(header.ends_with('\n'));
+        assert_eq!(header.trim_end(), "{'descr': '|u1', 'fortran_order': False, 'shape': (2, 3, 3), }");
+    }
+
+    #[test]
+    fn npy_data_holds_the_rows_in_order() {
+        let npy = npy(&grid(3, 2));
+        let (_, data) = split(&npy);
+        assert_eq!(data, [0, 0, 7, 1, 0, 7, 2, 0, 7, 0, 1, 7, 1, 1, 7, 2, 1, 7]);
+    }
+
+    #[test]
+    fn npy_data_starts_aligned_to_64_bytes() {
+        for (n_x, n_y) in [(0, 0), (1, 1), (3, 2), (12345, 1), (7, 999)] {
+            let npy = npy(&grid(n_x, n_y));
+            let (header, data) = split(&npy);
+            assert_eq!((10 + header.len()) % 64, 0, "{}x{}", n_x, n_y);
+            assert_eq!(data.len(), n_x * n_y * 3);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{render_inputs, Args};
+use crate::error::Error;
+use clap::Parser;
+use std::ffi::{c_char, c_int, c_uint, CStr, CString};
+use std::panic;
+
+// Lines rendered like the command line tool with these arguments, in true
+// colour whatever the process is attached to. Only existing local files are
+// read: the path is made absolute, so that `-`, URLs and glob patterns are
+// not taken as standard input, downloads or sets of files.
+fn render(path: &str, cols: c_uint, rows: c_uint) -> Result<String, Error> {
+    let path = std::fs::canonicalize(path)
+        .ok()
+        .filter(|p| p.is_file())
+        .and_then(|p| p.to_str().map(String::from))
+        .ok_or_else(|| Error::Usage(format!("Not a local image file: {}", path)))?;
+    let mut argv = vec![String::from("tcolr"), String::from("--color=always"), String::from("--colors=true"), String::from("--quiet")];
+    if cols > 0 {
+        argv.push(format!("--cols={}", cols));
+    }
+    if rows > 0 {
+        argv.push(format!("--rows={}", rows));
+    }
+    argv.push(String::from("--"));
+    argv.push(path.clone());
+    let args = Args::try_parse_from(argv).map_err(|e| Error::Usage(e.to_string()))?.with_preview_defaults();
+    return Ok(render_inputs(&[path], &args)?.join("\n"));
+}
+
+/// Renders the image file at `path` to fit `cols` x `rows` terminal cells
+/// and stores the lines of ANSI text in `*out_buf`, to be released with
+/// `tcolr_free`. Unlike the command line tool, `path` must name an existing
+/// local file: URLs, glob patterns and `-` for standard input are rejected.
+/// An axis given as 0 is unlimited, and with both 0 the image is sampled in
+/// the default chunks. Returns 0 on success, and otherwise the exit code of
+/// the command line tool with the error message in `*out_buf`, 5 if
+/// rendering panicked.
+///
+/// # Safety
+///
+/// `path` must be a NUL terminated string and `out_buf` must point to
+/// writable memory for a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tcolr_render_file(path: *const c_char, cols: c_uint, rows: c_uint, out_buf: *mut *mut c_char) -> c_int {
+    if path.is_null() || out_buf.is_null() {
+        return Error::Usage(String::new()).exit_code();
+    }
+    let path = CStr::from_ptr(path);
+    // Unwinding into the caller across `extern "C"` would abort it.
+    let result = panic::catch_unwind(|| match path.to_str() {
+        Ok(path) => render(path, cols, rows),
+        Err(_) => Err(Error::Usage(String::from("The path is not valid UTF-8")))
+    }).unwrap_or_else(|_| Err(Error::Render(String::from("Rendering failed unexpectedly"))));
+    let (code, text) = match result {
+        Ok(text) => (0, text),
+        Err(error) => (error.exit_code(), error.to_string())
+    };
+    // Rendered text holds no NUL characters, messages might.
+    *out_buf = CString::new(text.replace('\0', "")).unwrap_or_default().into_raw();
+    return code;
+}
+
+/// Releases a buffer returned by `tcolr_render_file`.
+///
+/// # Safety
+///
+/// `buf` must be null or a buffer returned by `tcolr_render_file` that was
+/// not released before.
+#[no_mangle]
+pub unsafe extern "C" fn tcolr_free(buf: *mut c_char) {
+    if !buf.is_null() {
+        drop(CString::from_raw(buf));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Exit code and text of rendering `path` through the C interface.
+    fn render_file(path: &str) -> (c_int, String) {
+        let path = CString::new(path).unwrap();
+        let mut buf = std::ptr::null_mut();
+        unsafe {
+            let code = tcolr_render_file(path.as_ptr(), 8, 4, &mut buf);
+            let text = CStr::from_ptr(buf).to_string_lossy().into_owned();
+            tcolr_free(buf);
+            return (code, text);
+        }
+    }
+
+    #[test]
+    fn renders_local_files() {
+        let (code, text) = render_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/disc.png"));
+        assert_eq!(code, 0, "{}", text);
+        assert!(text.contains('\x1b'));
+    }
+
+    #[test]
+    fn rejects_everything_but_local_files() {
+        for path in ["-", "https://example.com/image.png", "tests/fixtures/*.png", "tests/fixtures"] {
+            assert_eq!(render_file(path).0, 2, "{}", path);
+        }
+    }
+}
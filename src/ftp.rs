@@ -0,0 +1,167 @@
+use crate::error::BoxError;
+use crate::input::percent_decode;
+#[cfg(not(target_arch = "wasm32"))]
+use native_tls::{TlsConnector, TlsStream};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Schemes of files downloaded over FTP, `ftps` securing it with explicit TLS.
+pub const SCHEMES: [&str; 2] = ["ftp://", "ftps://"];
+
+// Control and data connections, in the clear or upgraded to TLS.
+enum Stream {
+    Plain(TcpStream),
+    #[cfg(not(target_arch = "wasm32"))]
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        return match self {
+            Stream::Plain(s) => s.read(buf),
+            #[cfg(not(target_arch = "wasm32"))]
+            Stream::Tls(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        return match self {
+            Stream::Plain(s) => s.write(buf),
+            #[cfg(not(target_arch = "wasm32"))]
+            Stream::Tls(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return match self {
+            Stream::Plain(s) => s.flush(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Stream::Tls(s) => s.flush(),
+        }
+    }
+}
+
+fn connect(host: &str, port: u16, timeout: Option<Duration>) -> Result<TcpStream, BoxError> {
+    let address = (host, port).to_socket_addrs()?.next().ok_or(format!("Unable to resolve {}", host))?;
+    let stream = match timeout {
+        Some(t) => TcpStream::connect_timeout(&address, t)?,
+        None => TcpStream::connect(address)?
+    };
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+    return Ok(stream);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn secure(stream: TcpStream, host: &str) -> Result<Stream, BoxError> {
+    return Ok(Stream::Tls(Box::new(TlsConnector::new()?.connect(host, stream)?)));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn secure(_stream: TcpStream, _host: &str) -> Result<Stream, BoxError> {
+    return Err("TLS is not available in WebAssembly".into());
+}
+
+struct Control {
+    stream: Stream,
+}
+
+impl Control {
+    // Reads a reply, skipping the continuation lines of multi-line replies,
+    // and returns its code and the text of its last line.
+    fn reply(&mut self) -> Result<(u32, String), BoxError> {
+        loop {
+            let mut line = Vec::new();
+            let mut byte = [0u8; 1];
+            while !line.ends_with(b"\r\n") {
+                if self.stream.read(&mut byte)? == 0 {
+                    return Err("The server closed the connection".into());
+                }
+                line.push(byte[0]);
+            }
+            let line = String::from_utf8_lossy(&line).trim_end().to_owned();
+            if line.len() >= 4 && line.as_bytes()[3] == b' ' {
+                if let Ok(code) = line[.. 3].parse() {
+                    return Ok((code, line));
+                }
+            }
+        }
+    }
+
+    // Sends `command` and fails unless the reply has one of the `expected` codes.
+    fn command(&mut self, command: &str, expected: &[u32]) -> Result<(u32, String), BoxError> {
+        self.stream.write_all(format!("{}\r\n", command).as_bytes())?;
+        return self.expect(expected);
+    }
+
+    fn expect(&mut self, expected: &[u32]) -> Result<(u32, String), BoxError> {
+        let (code, text) = self.reply()?;
+        if !expected.contains(&code) {
+            return Err(text.into());
+        }
+        return Ok((code, text));
+    }
+}
+
+// Port of the data connection from a `227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)`
+// reply. Its address is ignored in favour of the host of the control
+// connection, which also works behind NAT.
+fn passive_port(reply: &str) -> Option<u16> {
+    let inner = reply.split_once('(')?.1.split_once(')')?.0;
+    let numbers: Vec<u16> = inner.split(',').map(|n| n.trim().parse().ok()).collect::<Option<_>>()?;
+    return match numbers[..] {
+        [_, _, _, _, high, low] => Some(high * 256 + low),
+        _ => None
+    };
+}
+
+/// Contents of the file at `ftp://[user[:password]@]host[:port]/path`,
+/// logging in anonymously without a user.
+pub fn get(uri: &str, timeout: Option<Duration>) -> Result<Vec<u8>, BoxError> {
+    let tls = uri.starts_with("ftps://");
+    let rest = SCHEMES.iter().find_map(|s| uri.strip_prefix(s)).unwrap_or(uri);
+    let (authority, path) = rest.split_once('/').ok_or("Expected a path after the host")?;
+    let path = String::from_utf8(percent_decode(path))?;
+    let (credentials, address) = authority.rsplit_once('@').unwrap_or(("", authority));
+    let (user, password) = match credentials.split_once(':') {
+        Some((user, password)) => (user.to_owned(), password.to_owned()),
+        None if credentials.is_empty() => (String::from("anonymous"), String::from("anonymous@")),
+        None => (credentials.to_owned(), String::new())
+    };
+    let (user, password) = (String::from_utf8(percent_decode(&user))?, String::from_utf8(percent_decode(&password))?);
+    let (host, port) = match address.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse::<u16>()?),
+        None => (address, 21)
+    };
+
+    let mut control = Control { stream: Stream::Plain(connect(host, port, timeout)?) };
+    control.expect(&[220])?;
+    if tls {
+        control.command("AUTH TLS", &[234])?;
+        control.stream = match control.stream {
+            Stream::Plain(s) => secure(s, host)?,
+            #[cfg(not(target_arch = "wasm32"))]
+            s => s
+        };
+        control.command("PBSZ 0", &[200])?;
+        control.command("PROT P", &[200])?;
+    }
+    if control.command(&format!("USER {}", user), &[230, 331])?.0 == 331 {
+        control.command(&format!("PASS {}", password), &[230, 202])?;
+    }
+    control.command("TYPE I", &[200])?;
+    let (_, pasv) = control.command("PASV", &[227])?;
+    let data_port = passive_port(&pasv).ok_or(format!("Unexpected reply to PASV: {}", pasv))?;
+    let data = connect(host, data_port, timeout)?;
+    control.command(&format!("RETR {}", path), &[125, 150])?;
+    let mut data = if tls { secure(data, host)? } else { Stream::Plain(data) };
+    let mut body = Vec::new();
+    data.read_to_end(&mut body)?;
+    drop(data);
+    control.expect(&[226, 250])?;
+    let _ = control.command("QUIT", &[221]);
+    return Ok(body);
+}
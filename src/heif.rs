@@ -0,0 +1,23 @@
+use image::{DynamicImage, RgbaImage};
+use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+/// Decodes the primary image of a HEIF file, such as the HEIC photos of
+/// phones, through libheif.
+pub fn decode(data: &[u8]) -> Result<DynamicImage, libheif_rs::HeifError> {
+    let context = HeifContext::read_from_bytes(data)?;
+    let handle = context.primary_image_handle()?;
+    let image = LibHeif::new().decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)?;
+    let plane = image.planes().interleaved.ok_or_else(|| libheif_rs::HeifError {
+        code: libheif_rs::HeifErrorCode::DecoderPluginError,
+        sub_code: libheif_rs::HeifErrorSubCode::Unspecified,
+        message: String::from("No interleaved RGBA plane"),
+    })?;
+    let (width, height) = (plane.width, plane.height);
+    // Rows may be padded beyond their pixels.
+    let row = width as usize * 4;
+    let mut pixels = Vec::with_capacity(row * height as usize);
+    for y in 0 .. height as usize {
+        pixels.extend_from_slice(&plane.data[y * plane.stride .. y * plane.stride + row]);
+    }
+    return Ok(DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, pixels).expect("Rows hold width x height RGBA pixels")));
+}
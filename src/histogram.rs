@@ -0,0 +1,47 @@
+use crate::palette;
+use crate::palette::ColorDepth;
+use ansi_term::Colour;
+use image::DynamicImage;
+
+// Glyphs for bars filled to 1/8 .. 8/8 of a cell.
+const BAR_EIGHTHS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Counts of the 256 values of the red, green and blue channels and of the
+/// luminance of the opaque pixels of `image`.
+pub fn compute(image: &DynamicImage) -> [[u64; 256]; 4] {
+    let mut counts = [[0u64; 256]; 4];
+    for p in image.to_rgba8().pixels().filter(|p| p[3] > 0) {
+        let luminance = 0.2126 * p[0] as f64 + 0.7152 * p[1] as f64 + 0.0722 * p[2] as f64;
+        counts[0][p[0] as usize] += 1;
+        counts[1][p[1] as usize] += 1;
+        counts[2][p[2] as usize] += 1;
+        counts[3][luminance.round().min(255.0) as usize] += 1;
+    }
+    return counts;
+}
+
+/// Bar chart of `counts` grouped into `bins` columns, `height` rows high,
+/// with the bars painted in `colour`.
+pub fn render(counts: &[u64; 256], bins: usize, height: usize, colour: Colour, depth: ColorDepth) -> Vec<String> {
+    let bins = bins.clamp(1, counts.len());
+    let sums: Vec<u64> = (0 .. bins)
+        .map(|b| counts[b * counts.len() / bins .. (b + 1) * counts.len() / bins].iter().sum())
+        .collect();
+    let max = sums.iter().copied().max().unwrap_or(0).max(1);
+    // Bar heights in eighths of a row.
+    let bars: Vec<usize> = sums.iter()
+        .map(|s| (*s as f64 / max as f64 * (height * 8) as f64).round() as usize)
+        .collect();
+    let style = palette::foreground(colour, depth);
+    return (0 .. height).rev()
+        .map(|row| {
+            let line: String = bars.iter()
+                .map(|bar| match bar.saturating_sub(row * 8) {
+                    0 => ' ',
+                    n => BAR_EIGHTHS[n.min(8) - 1]
+                })
+                .collect();
+            style.paint(line).to_string()
+        })
+        .collect();
+}
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod client;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use client::{get, open, Body};
+
+/// Settings of the HTTP client used for remote images.
+#[derive(Clone, Debug, Default)]
+pub struct HttpOptions {
+    /// Limit for a whole request, unlimited if `None`.
+    pub timeout: Option<Duration>,
+    /// Additional attempts after a failed request.
+    pub retries: u32,
+    /// Proxy for all requests, otherwise the proxy environment variables apply.
+    pub proxy: Option<String>,
+    /// Extra headers sent with every request.
+    pub headers: Vec<(String, String)>,
+    /// Show a progress bar on stderr while downloading, if it is a terminal.
+    pub progress: bool,
+    /// Downloads with larger bodies are abandoned.
+    pub max_bytes: Option<u64>,
+}
+
+/// Parses a `Name: value` header.
+pub fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s.split_once(':').ok_or(format!("Expected a header as Name:Value: {}", s))?;
+    let (name, value) = (name.trim(), value.trim());
+    #[cfg(not(target_arch = "wasm32"))]
+    client::validate_header(name, value)?;
+    return Ok((name.to_owned(), value.to_owned()));
+}
+
+/// WebAssembly has no network access of its own, images are passed in.
+#[cfg(target_arch = "wasm32")]
+pub fn get(_url: &str, _headers: &[(String, String)], _options: &HttpOptions) -> Result<(bytes::Bytes, Option<String>), Box<dyn std::error::Error + Send + Sync>> {
+    return Err("Downloads are not available in WebAssembly".into());
+}
+
+/// WebAssembly has no network access of its own, so there is never a body.
+#[cfg(target_arch = "wasm32")]
+pub type Body = std::io::Empty;
+
+#[cfg(target_arch = "wasm32")]
+pub fn open(_url: &str, _headers: &[(String, String)], _options: &HttpOptions) -> Result<(Body, Option<String>), Box<dyn std::error::Error + Send + Sync>> {
+    return Err("Downloads are not available in WebAssembly".into());
+}
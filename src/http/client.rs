@@ -0,0 +1,184 @@
+use super::HttpOptions;
+use bytes::Bytes;
+use reqwest::blocking::{Client, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::io::{IsTerminal, Read};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+const USER_AGENT: &str = concat!("tcolr/", env!("CARGO_PKG_VERSION"));
+
+// Delay before the first retry, doubled for every further attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Checks that a header can be sent as given.
+pub fn validate_header(name: &str, value: &str) -> Result<(), String> {
+    HeaderName::from_bytes(name.as_bytes()).map_err(|e| format!("Invalid header name {}: {}", name, e))?;
+    HeaderValue::from_str(value).map_err(|e| format!("Invalid value for header {}: {}", name, e))?;
+    return Ok(());
+}
+
+fn build(options: &HttpOptions) -> Result<Client, reqwest::Error> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &options.headers {
+        // Both were validated when parsing the arguments.
+        if let (Ok(n), Ok(v)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            headers.append(n, v);
+        }
+    }
+    let mut builder = Client::builder()
+        .user_agent(USER_AGENT)
+        .default_headers(headers)
+        .timeout(options.timeout);
+    if let Some(proxy) = &options.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    return builder.build();
+}
+
+// The client is configured once from the arguments and shared by all requests.
+fn client(options: &HttpOptions) -> Result<&'static Client, String> {
+    static CLIENT: OnceLock<Result<Client, String>> = OnceLock::new();
+    return CLIENT.get_or_init(|| build(options).map_err(|e| e.to_string()))
+        .as_ref()
+        .map_err(|e| e.clone());
+}
+
+// Only timeouts, failed connections and overloaded servers may succeed when
+// tried again. Other errors, like a malformed URL or too many redirects, fail
+// the same way every time.
+fn is_transient(result: &Result<Response, reqwest::Error>) -> bool {
+    return match result {
+        Ok(r) => r.status().is_server_error() || r.status() == reqwest::StatusCode::TOO_MANY_REQUESTS,
+        Err(e) => e.is_timeout() || e.is_connect()
+    }
+}
+
+/// Body of `url` and its `Content-Type`, requested with `headers` on top of
+/// those of the options, retrying timeouts, connection failures and server
+/// errors with exponential backoff.
+pub fn get(url: &str, headers: &[(String, String)], options: &HttpOptions) -> Result<(Bytes, Option<String>), Box<dyn std::error::Error + Send + Sync>> {
+    let (mut body, content_type) = open(url, headers, options)?;
+    let mut bytes = Vec::with_capacity(body.total.unwrap_or(0).min(1 << 26) as usize);
+    body.read_to_end(&mut bytes)?;
+    return Ok((Bytes::from(bytes), content_type));
+}
+
+/// Like `get`, but returns the body unread, to be consumed as it arrives.
+pub fn open(url: &str, headers: &[(String, String)], options: &HttpOptions) -> Result<(Body, Option<String>), Box<dyn std::error::Error + Send + Sync>> {
+    let client = client(options)?;
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt = 0;
+    loop {
+        let result = headers.iter().fold(client.get(url), |request, (name, value)| request.header(name, value)).send();
+        if attempt < options.retries && is_transient(&result) {
+            attempt += 1;
+            thread::sleep(backoff);
+            backoff *= 2;
+            continue;
+        }
+        let response = result?.error_for_status()?;
+        let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_owned);
+        let body = Body::new(response, options.progress && std::io::stderr().is_terminal(), options.max_bytes)?;
+        return Ok((body, content_type));
+    }
+}
+
+// Width of the progress bar in cells.
+const BAR_WIDTH: usize = 30;
+
+fn mebibytes(n: usize) -> f64 {
+    return n as f64 / (1024.0 * 1024.0);
+}
+
+fn draw_progress(read: usize, total: Option<u64>) {
+    let line = match total {
+        Some(t) if t > 0 => {
+            let fraction = (read as f64 / t as f64).min(1.0);
+            let filled = (fraction * BAR_WIDTH as f64) as usize;
+            format!("[{}{}] {:3.0}% {:.1}/{:.1} MiB",
+                "#".repeat(filled), ".".repeat(BAR_WIDTH - filled), fraction * 100.0, mebibytes(read), mebibytes(t as usize))
+        },
+        _ => format!("{:.1} MiB", mebibytes(read))
+    };
+    eprint!("\r\x1b[K{}", line);
+}
+
+/// Body of a response, which shows the download progress while it is read
+/// and fails once it exceeds `--max-bytes`.
+pub struct Body {
+    response: Response,
+    total: Option<u64>,
+    read: usize,
+    progress: bool,
+    max_bytes: Option<u64>,
+}
+
+impl Body {
+    fn new(response: Response, progress: bool, max_bytes: Option<u64>) -> std::io::Result<Body> {
+        let body = Body { total: response.content_length(), response, read: 0, progress, max_bytes };
+        body.within_max_bytes(body.total.unwrap_or(0))?;
+        return Ok(body);
+    }
+
+    fn within_max_bytes(&self, size: u64) -> std::io::Result<()> {
+        return match self.max_bytes {
+            Some(max) if size > max => Err(std::io::Error::other(format!("The body has {} bytes, more than the limit of {} set by --max-bytes", size, max))),
+            _ => Ok(())
+        }
+    }
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.response.read(buf)?;
+        self.read += n;
+        // The content length may be missing or wrong.
+        self.within_max_bytes(self.read as u64)?;
+        if self.progress && n > 0 {
+            draw_progress(self.read, self.total);
+        }
+        return Ok(n);
+    }
+}
+
+// Decoders may stop reading before the end, so the bar is cleared when the
+// body is dropped rather than at its end.
+impl Drop for Body {
+    fn drop(&mut self) {
+        if self.progress && self.read > 0 {
+            eprint!("\r\x1b[K");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refused_connections_are_retried() {
+        // Nothing listens on port 1.
+        let result = Client::new().get("http://127.0.0.1:1/").send();
+        assert!(is_transient(&result));
+    }
+
+    #[test]
+    fn malformed_responses_are_not_retried() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            use std::io::Write;
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0u8; 1024]);
+            let _ = stream.write_all(b"not HTTP at all\r\n\r\n");
+        });
+        let result = Client::new().get(url).send();
+        server.join().unwrap();
+        assert!(result.as_ref().is_err_and(|e| e.is_request()));
+        assert!(!is_transient(&result));
+    }
+}
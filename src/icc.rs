@@ -0,0 +1,29 @@
+use image::DynamicImage;
+use qcms::{DataType, Intent, Profile, Transform};
+
+/// Converts `image` from the colour space described by the ICC `profile` to
+/// sRGB. 16 bit images are converted to 8 bits, and floating point images,
+/// which hold linear light rather than encoded colours, as well as images
+/// whose profile cannot be read, are returned unchanged.
+pub fn to_srgb(image: DynamicImage, profile: &[u8]) -> DynamicImage {
+    let input = match Profile::new_from_slice(profile, false) {
+        Some(input) if !input.is_sRGB() => input,
+        _ => return image
+    };
+    let output = Profile::new_sRGB();
+    let (mut pixels, ty) = match image {
+        DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgb16(_) => (DynamicImage::ImageRgb8(image.to_rgb8()), DataType::RGB8),
+        DynamicImage::ImageRgba8(_) | DynamicImage::ImageRgba16(_) => (DynamicImage::ImageRgba8(image.to_rgba8()), DataType::RGBA8),
+        image => return image
+    };
+    let transform = match Transform::new(&input, &output, ty, Intent::Perceptual) {
+        Some(transform) => transform,
+        None => return pixels
+    };
+    match &mut pixels {
+        DynamicImage::ImageRgb8(buf) => transform.apply(buf),
+        DynamicImage::ImageRgba8(buf) => transform.apply(buf),
+        _ => {}
+    }
+    return pixels;
+}
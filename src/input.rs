@@ -0,0 +1,646 @@
+use crate::cache;
+use crate::container;
+use crate::error::Error;
+use crate::http;
+use crate::ftp;
+use crate::http::HttpOptions;
+use crate::page;
+use crate::ssh;
+use crate::timing;
+use crate::timing::Stage;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use bytes::Bytes;
+#[cfg(feature = "clipboard")]
+use image::ImageEncoder;
+use image::{DynamicImage, ImageDecoder, ImageError, ImageFormat, Limits};
+use image::ImageReader as Reader;
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Settings for fetching inputs and decoding those that are not plain raster images.
+#[derive(Clone, Debug)]
+pub struct DecodeOptions {
+    /// Downloads are cached on disk and reused for this long, never if `None`.
+    pub cache_ttl: Option<Duration>,
+    pub http: HttpOptions,
+    /// Vector images are rasterized to a whole number of chunks of this size.
+    #[cfg_attr(not(feature = "svg"), allow(dead_code))]
+    pub chunk_size: (u32, u32),
+    /// Position of the frame extracted from videos.
+    #[cfg_attr(not(feature = "video"), allow(dead_code))]
+    pub timestamp: Option<Duration>,
+    /// Zero-based index of the page rendered from PDF documents and TIFF files.
+    pub page: usize,
+    /// Width of the size picked from icons with several, the largest for `u32::MAX`.
+    pub icon_size: u32,
+    /// Images with more pixels are rejected before decoding. Otherwise the
+    /// decoder's default allocation limit applies.
+    pub max_pixels: Option<u64>,
+    /// Inputs with more bytes are rejected before decoding.
+    pub max_bytes: Option<u64>,
+    /// Remote images are only loaded from the cache, never downloaded.
+    pub cached_only: bool,
+    /// Web pages are replaced by the image they show, rather than rejected.
+    pub extract_page_image: bool,
+}
+
+/// Input naming standard input.
+pub const STDIN: &str = "-";
+
+/// Input naming the image on the system clipboard.
+pub const CLIPBOARD: &str = "clipboard:";
+
+const VIDEO_EXTENSIONS: [&str; 7] = [".mp4", ".m4v", ".mkv", ".webm", ".mov", ".avi", ".mpg"];
+
+fn is_video(path: &str) -> bool {
+    let path = path.to_lowercase();
+    return VIDEO_EXTENSIONS.iter().any(|e| path.ends_with(e));
+}
+
+const RAW_EXTENSIONS: [&str; 11] = [".cr2", ".cr3", ".nef", ".nrw", ".arw", ".dng", ".raf", ".orf", ".rw2", ".pef", ".srw"];
+
+fn is_raw(path: &str) -> bool {
+    let path = path.to_lowercase();
+    return RAW_EXTENSIONS.iter().any(|e| path.ends_with(e));
+}
+
+fn is_svg_path(path: &str) -> bool {
+    return path.to_lowercase().ends_with(".svg");
+}
+
+fn is_image_path(path: &std::path::Path) -> bool {
+    let name = path.to_string_lossy().to_lowercase();
+    return image::ImageFormat::from_path(path).is_ok()
+        || [".svg", ".heic", ".heif", ".pdf"].iter().any(|e| name.ends_with(e))
+        || is_video(&name)
+        || is_raw(&name);
+}
+
+fn has_glob_pattern(input: &str) -> bool {
+    return !input.contains("://") && input.contains(['*', '?', '[']);
+}
+
+/// Replaces directories and glob patterns in `inputs` by the image files they
+/// contain, in sorted order. Also returns whether any input was expanded.
+pub fn expand(inputs: &[String]) -> Result<(Vec<String>, bool), Error> {
+    let mut expanded = Vec::new();
+    let mut any_expanded = false;
+    for input in inputs {
+        let mut paths = if has_glob_pattern(input) {
+            glob::glob(input)
+                .map_err(|e| Error::Usage(format!("Invalid glob pattern {}: {}", input, e)))?
+                .filter_map(Result::ok)
+                .filter(|p| p.is_file())
+                .collect::<Vec<_>>()
+        } else if std::path::Path::new(input).is_dir() {
+            std::fs::read_dir(input)
+                .map_err(|e| Error::fetch(input, e))?
+                .filter_map(Result::ok)
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && is_image_path(p))
+                .collect::<Vec<_>>()
+        } else {
+            expanded.push(input.clone());
+            continue;
+        };
+        paths.sort();
+        any_expanded = true;
+        expanded.extend(paths.iter().map(|p| p.to_string_lossy().into_owned()));
+    }
+    return Ok((expanded, any_expanded));
+}
+
+/// Applies `fetch` to every uri on up to `jobs` threads and returns the
+/// results in the order of `uris`.
+pub fn fetch_all<T: Send, F: Fn(&String) -> T + Sync>(uris: &[String], jobs: usize, fetch: F) -> Vec<T> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<T>>> = Mutex::new(uris.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0 .. jobs.clamp(1, uris.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= uris.len() {
+                    break;
+                }
+                let result = fetch(&uris[index]);
+                if let Ok(mut r) = results.lock() {
+                    r[index] = Some(result);
+                }
+            });
+        }
+    });
+    return results.into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .map(|r| r.expect("Every uri is fetched exactly once"))
+        .collect();
+}
+
+/// Decodes the `%XX` escapes of a URL, leaving malformed ones as they are.
+pub fn percent_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1 .. i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            },
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    return decoded;
+}
+
+pub fn is_remote(uri: &str) -> bool {
+    return ["http://", "https://", "s3://", "gs://"].iter()
+        .chain(&ssh::SCHEMES)
+        .chain(&ftp::SCHEMES)
+        .any(|s| uri.starts_with(s));
+}
+
+pub fn get_image(image_url: &String, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    let image = match image_url {
+        u if u == STDIN || u == CLIPBOARD => fetch_and_decode(u, options),
+        u if !u.contains("://") && is_video(u) => timing::measure(Stage::Decode, || get_image_from_video(u, options)),
+        u if !u.starts_with("data:") && is_raw(u) => get_image_from_raw(u, options),
+        u if !u.starts_with("data:") && !u.contains("://") && !is_svg_path(u) => get_image_from_file(u, options),
+        u if u.starts_with("file://") => get_image(&u.strip_prefix("file://").unwrap_or_default().to_owned(), options),
+        u => fetch_and_decode(u, options)
+    };
+    return image;
+}
+
+fn fetch_and_decode(uri: &String, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    let downloads = (uri.starts_with("http://") || uri.starts_with("https://"))
+        && !options.cached_only
+        && !options.cache_ttl.is_some_and(|ttl| cache::contains(&cache_key(uri, options), ttl));
+    if downloads {
+        return download_and_decode(uri, options);
+    }
+    let (bytes, media_type) = timing::measure(Stage::Fetch, || get_typed_bytes(uri, options))?;
+    return timing::measure(Stage::Decode, || decode_typed(bytes, media_type.as_deref(), uri, options));
+}
+
+// Formats whose decoders read front to back, so that they can decode a
+// download while it arrives.
+const STREAMED_FORMATS: [ImageFormat; 3] = [ImageFormat::Png, ImageFormat::Gif, ImageFormat::Jpeg];
+
+// Decodes PNG, GIF and JPEG images from HTTP as they are downloaded. Anything
+// else is read whole and decoded like other inputs.
+fn download_and_decode(url: &str, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    let (body, media_type) = timing::measure(Stage::Fetch, || http::open(url, &[], &options.http))
+        .map_err(|e| Error::fetch(url, e))?;
+    let mut body = Forward::new(body, options.cache_ttl.is_some());
+    let head = body.fill_buf().map_err(|e| Error::fetch(url, e))?;
+    let format = match image::guess_format(head) {
+        Ok(format) if STREAMED_FORMATS.contains(&format) => format,
+        _ => {
+            let mut bytes = Vec::new();
+            timing::measure(Stage::Fetch, || body.read_to_end(&mut bytes)).map_err(|e| Error::fetch(url, e))?;
+            let bytes = Bytes::from(bytes);
+            let image = timing::measure(Stage::Decode, || decode_typed(bytes.clone(), media_type.as_deref(), url, options))?;
+            if options.cache_ttl.is_some() {
+                cache::store(&cache_key(url, options), &bytes, media_type.as_deref());
+            }
+            return Ok(image);
+        }
+    };
+    // Reading the body may fail as well as decoding it.
+    let error = |e: ImageError| match e {
+        ImageError::IoError(e) => Error::fetch(url, e),
+        e => Error::decode(url, e)
+    };
+    let image = timing::measure(Stage::Decode, || {
+        let mut reader = Reader::with_format(&mut body, format);
+        if options.max_pixels.is_some() {
+            let mut limits = Limits::default();
+            limits.max_alloc = None;
+            reader.limits(limits);
+        }
+        let decoder = reader.into_decoder().map_err(error)?;
+        within_max_pixels(decoder.dimensions(), url, options)?;
+        return decode_to_srgb(decoder).map_err(error);
+    })?;
+    // The decoder may stop before the end, but only whole bodies are cached.
+    if let Some(kept) = body.kept.as_mut() {
+        if body.reader.read_to_end(kept).is_ok() {
+            cache::store(&cache_key(url, options), &Bytes::from(std::mem::take(kept)), media_type.as_deref());
+        }
+    }
+    return Ok(image);
+}
+
+/// Buffered reader of a download that only goes forward. The image decoders
+/// require `Seek`, but those of `STREAMED_FORMATS` at most ask for the
+/// position.
+struct Forward {
+    reader: BufReader<http::Body>,
+    position: u64,
+    /// Everything read so far, to be cached.
+    kept: Option<Vec<u8>>,
+}
+
+impl Forward {
+    fn new(body: http::Body, keep: bool) -> Forward {
+        return Forward { reader: BufReader::new(body), position: 0, kept: if keep { Some(Vec::new()) } else { None } };
+    }
+}
+
+impl Read for Forward {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.position += n as u64;
+        if let Some(kept) = self.kept.as_mut() {
+            kept.extend_from_slice(&buf[.. n]);
+        }
+        return Ok(n);
+    }
+}
+
+impl BufRead for Forward {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        return self.reader.fill_buf();
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if let Some(kept) = self.kept.as_mut() {
+            kept.extend_from_slice(&self.reader.buffer()[.. amount]);
+        }
+        self.position += amount as u64;
+        self.reader.consume(amount);
+    }
+}
+
+impl Seek for Forward {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        return match pos {
+            SeekFrom::Current(0) => Ok(self.position),
+            SeekFrom::Start(p) if p == self.position => Ok(p),
+            _ => Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Downloads are decoded front to back and cannot seek"))
+        }
+    }
+}
+
+#[cfg(feature = "video")]
+fn get_image_from_video(path: &str, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    return crate::video::frame_at(path, options.timestamp)
+        .map_err(|e| Error::decode(path, e));
+}
+
+#[cfg(not(feature = "video"))]
+fn get_image_from_video(path: &str, _options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    return Err(Error::decode(path, "Video input requires the `video` feature"));
+}
+
+#[cfg(feature = "raw")]
+fn get_image_from_raw(uri: &str, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    let bytes = timing::measure(Stage::Fetch, || get_bytes(&uri.to_owned(), options))?;
+    return timing::measure(Stage::Decode, || match crate::raw::preview_offset(&bytes) {
+        Some(offset) => decode(bytes.slice(offset ..), Format::Known(ImageFormat::Jpeg), uri, options),
+        None => Err(Error::decode(uri, "No JPEG preview found in the camera RAW file"))
+    });
+}
+
+#[cfg(not(feature = "raw"))]
+fn get_image_from_raw(uri: &str, _options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    return Err(Error::decode(uri, "Camera RAW input requires the `raw` feature"));
+}
+
+/// Raw, undecoded contents of the resource at `image_url`.
+pub fn get_bytes(image_url: &String, options: &DecodeOptions) -> Result<Bytes, Error> {
+    return get_typed_bytes(image_url, options).map(|(bytes, _)| bytes);
+}
+
+// Raw contents of the resource at `image_url`, with the media type declared
+// for them by a server or a data URI.
+fn get_typed_bytes(image_url: &String, options: &DecodeOptions) -> Result<(Bytes, Option<String>), Error> {
+    let untyped = |bytes: Bytes| (bytes, None);
+    let bytes = match image_url {
+        u if u == STDIN => get_bytes_from_stdin().map(untyped),
+        u if u == CLIPBOARD => get_bytes_from_clipboard().map(untyped),
+        u if u.starts_with("data:") => get_bytes_from_data_uri(u),
+        u if !u.contains("://") => get_bytes_from_file(u, options).map(untyped),
+        u if u.starts_with("file://") => get_typed_bytes(&u.strip_prefix("file://").unwrap_or_default().to_owned(), options),
+        u if u.starts_with("s3://") || u.starts_with("gs://") => get_bytes_from_cloud(u, options),
+        u if ssh::SCHEMES.iter().any(|s| u.starts_with(s)) => get_bytes_from_ssh(u, options),
+        u if ftp::SCHEMES.iter().any(|s| u.starts_with(s)) => get_bytes_from_ftp(u, options),
+        u if is_remote(u) => get_bytes_from_https(u, options),
+        u => Err(Error::UnsupportedScheme {
+            scheme: u.split_once("://").map(|t| t.0).unwrap_or_default().to_owned(),
+            uri: u.clone()
+        })
+    };
+    return bytes.and_then(|b| within_max_bytes(b.0.len() as u64, image_url, options).map(|_| b));
+}
+
+fn within_max_bytes(size: u64, uri: &str, options: &DecodeOptions) -> Result<(), Error> {
+    return match options.max_bytes {
+        Some(max) if size > max => Err(Error::fetch(uri, format!("The input has {} bytes, more than the limit of {} set by --max-bytes", size, max))),
+        _ => Ok(())
+    }
+}
+
+fn is_svg(bytes: &Bytes) -> bool {
+    let head = &bytes[.. bytes.len().min(1024)];
+    return String::from_utf8_lossy(head).contains("<svg");
+}
+
+#[cfg(feature = "svg")]
+fn get_image_from_svg(bytes: Bytes, uri: &str, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    return crate::svg::rasterize(&bytes, options.chunk_size.0, options.chunk_size.1)
+        .map_err(|e| Error::decode(uri, e));
+}
+
+#[cfg(not(feature = "svg"))]
+fn get_image_from_svg(_bytes: Bytes, uri: &str, _options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    return Err(Error::decode(uri, "SVG input requires the `svg` feature"));
+}
+
+/// Decodes the contents of the resource at `uri`.
+pub fn get_image_from_bytes(bytes: Bytes, uri: &str, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    return decode_typed(bytes, None, uri, options);
+}
+
+// Decodes `bytes`, falling back on the format named by `media_type` if the
+// contents do not reveal it.
+fn decode_typed(bytes: Bytes, media_type: Option<&str>, uri: &str, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    if media_type.is_some_and(|t| essence(t) == "text/html") || page::is_html(&bytes) {
+        return get_image_from_page(&bytes, uri, options);
+    }
+    if is_svg(&bytes) || media_type.is_some_and(|t| essence(t) == "image/svg+xml") {
+        return get_image_from_svg(bytes, uri, options);
+    }
+    let hint = media_type.and_then(|t| ImageFormat::from_mime_type(essence(t)));
+    return decode(bytes, Format::Guessed(hint), uri, options);
+}
+
+// Fetches the image shown by the web page in `bytes`, resolving its URL
+// against the URL of the page.
+fn get_image_from_page(bytes: &Bytes, uri: &str, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    if !options.extract_page_image {
+        return Err(Error::decode(uri, "This is a web page rather than an image, --extract-page-image renders the image it shows"));
+    }
+    let src = page::image_url(&String::from_utf8_lossy(bytes)).ok_or_else(|| Error::decode(uri, "No image found on the web page"))?;
+    let url = match url::Url::parse(uri).and_then(|base| base.join(&src)) {
+        Ok(url) => url.to_string(),
+        Err(_) => src
+    };
+    // Pages are not followed any further.
+    let options = DecodeOptions { extract_page_image: false, ..options.clone() };
+    return get_image(&url, &options);
+}
+
+// Media type without its parameters, like `image/webp` of `image/webp; q=0.9`.
+fn essence(media_type: &str) -> &str {
+    return media_type.split(';').next().unwrap_or_default().trim();
+}
+
+const HEIF_BRANDS: [&[u8]; 6] = [b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx"];
+
+// HEIF images start with an `ftyp` box naming one of the HEVC brands.
+fn is_heif(bytes: &Bytes) -> bool {
+    return bytes.len() >= 12 && &bytes[4 .. 8] == b"ftyp" && HEIF_BRANDS.contains(&&bytes[8 .. 12]);
+}
+
+fn is_pdf(bytes: &Bytes) -> bool {
+    return bytes.starts_with(b"%PDF-");
+}
+
+#[cfg(feature = "pdf")]
+fn get_image_from_pdf(bytes: Bytes, uri: &str, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    return crate::pdf::rasterize(&bytes, options.page).map_err(|e| Error::decode(uri, e));
+}
+
+#[cfg(not(feature = "pdf"))]
+fn get_image_from_pdf(_bytes: Bytes, uri: &str, _options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    return Err(Error::decode(uri, "PDF input requires the `pdf` feature"));
+}
+
+#[cfg(feature = "heic")]
+fn get_image_from_heif(bytes: Bytes, uri: &str) -> Result<DynamicImage, Error> {
+    return crate::heif::decode(&bytes).map_err(|e| Error::decode(uri, e));
+}
+
+#[cfg(not(feature = "heic"))]
+fn get_image_from_heif(_bytes: Bytes, uri: &str) -> Result<DynamicImage, Error> {
+    return Err(Error::decode(uri, "HEIC input requires the `heic` feature"));
+}
+
+/// Format to decode as, either known for certain or guessed from the
+/// contents with an optional fallback.
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Known(ImageFormat),
+    Guessed(Option<ImageFormat>),
+}
+
+fn reader(bytes: Bytes, format: Format) -> std::io::Result<Reader<Cursor<Bytes>>> {
+    let mut reader = Reader::new(Cursor::new(bytes));
+    return match format {
+        Format::Known(f) => {
+            reader.set_format(f);
+            Ok(reader)
+        },
+        Format::Guessed(hint) => {
+            let mut reader = reader.with_guessed_format()?;
+            if let (None, Some(f)) = (reader.format(), hint) {
+                reader.set_format(f);
+            }
+            Ok(reader)
+        }
+    }
+}
+
+// Decodes `bytes` as `format`. With `--max-pixels` the dimensions are read
+// from the header first, so that an oversized image is rejected before
+// anything is allocated for it. HEIF images and PDF documents are not known
+// to the image crate and are recognised by their contents.
+fn decode(bytes: Bytes, format: Format, uri: &str, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    if is_heif(&bytes) {
+        return get_image_from_heif(bytes, uri);
+    }
+    if is_pdf(&bytes) {
+        return get_image_from_pdf(bytes, uri, options);
+    }
+    let mut image = reader(bytes.clone(), format).map_err(|e| Error::decode(uri, e))?;
+    match image.format() {
+        Some(ImageFormat::Tiff) if options.page > 0 => {
+            return container::tiff_page(&bytes, options.page, options.max_pixels).map_err(|e| Error::decode(uri, e));
+        },
+        Some(ImageFormat::Ico) => if let Some(entry) = container::ico_entry(&bytes, options.icon_size) {
+            return decode(Bytes::from(entry), Format::Known(ImageFormat::Ico), uri, options);
+        },
+        _ => {}
+    }
+    if options.max_pixels.is_some() {
+        let dimensions = reader(bytes, format)
+            .map_err(ImageError::IoError)
+            .and_then(|r| r.into_dimensions())
+            .map_err(|e| Error::decode(uri, e))?;
+        within_max_pixels(dimensions, uri, options)?;
+        let mut limits = Limits::default();
+        limits.max_alloc = None;
+        image.limits(limits);
+    }
+    return image.into_decoder().and_then(decode_to_srgb).map_err(|e| Error::decode(uri, e));
+}
+
+fn within_max_pixels((width, height): (u32, u32), uri: &str, options: &DecodeOptions) -> Result<(), Error> {
+    return match options.max_pixels {
+        Some(max) if width as u64 * height as u64 > max => Err(Error::decode(uri, format!("The image has {}x{} pixels, more than the limit of {} set by --max-pixels", width, height, max))),
+        _ => Ok(())
+    }
+}
+
+// Decodes the image and converts colours described by an embedded ICC
+// profile to sRGB, so that wide gamut photos are neither over saturated
+// nor washed out.
+#[cfg(feature = "icc")]
+fn decode_to_srgb(mut decoder: impl ImageDecoder) -> Result<DynamicImage, ImageError> {
+    let profile = decoder.icc_profile().ok().flatten();
+    let image = DynamicImage::from_decoder(decoder)?;
+    return Ok(match profile {
+        Some(profile) => crate::icc::to_srgb(image, &profile),
+        None => image
+    });
+}
+
+#[cfg(not(feature = "icc"))]
+fn decode_to_srgb(decoder: impl ImageDecoder) -> Result<DynamicImage, ImageError> {
+    return DynamicImage::from_decoder(decoder);
+}
+
+fn get_bytes_from_https(url: &str, options: &DecodeOptions) -> Result<(Bytes, Option<String>), Error> {
+    return download(url, || http::get(url, &[], &options.http).map_err(|e| Error::fetch(url, e)), options);
+}
+
+fn get_bytes_from_ssh(uri: &str, options: &DecodeOptions) -> Result<(Bytes, Option<String>), Error> {
+    return download(uri, || ssh::get(uri).map(|b| (Bytes::from(b), None)).map_err(|e| Error::fetch(uri, e)), options);
+}
+
+fn get_bytes_from_ftp(uri: &str, options: &DecodeOptions) -> Result<(Bytes, Option<String>), Error> {
+    return download(uri, || ftp::get(uri, options.http.timeout).map(|b| (Bytes::from(b), None)).map_err(|e| Error::fetch(uri, e)), options);
+}
+
+#[cfg(feature = "cloud")]
+fn get_bytes_from_cloud(uri: &str, options: &DecodeOptions) -> Result<(Bytes, Option<String>), Error> {
+    return download(uri, || {
+        let request = crate::cloud::request(uri).map_err(Error::Usage)?;
+        http::get(&request.url, &request.headers, &options.http).map_err(|e| Error::fetch(uri, e))
+    }, options);
+}
+
+#[cfg(not(feature = "cloud"))]
+fn get_bytes_from_cloud(uri: &str, _options: &DecodeOptions) -> Result<(Bytes, Option<String>), Error> {
+    return Err(Error::fetch(uri, "S3 and Cloud Storage input requires the `cloud` feature"));
+}
+
+// Key of `uri` in the cache. Headers given with `--header` may change the
+// response, like an authorization or a language, so they are part of it.
+fn cache_key(uri: &str, options: &DecodeOptions) -> String {
+    return options.http.headers.iter().fold(uri.to_owned(), |key, (name, value)| format!("{}\n{}: {}", key, name, value));
+}
+
+// Downloads the body of `uri` through `get`, or loads it from the cache, keyed
+// by `uri` rather than by the possibly signed URL that is requested. Cached
+// bodies are stored with their content type.
+fn download<F: FnOnce() -> Result<(Bytes, Option<String>), Error>>(uri: &str, get: F, options: &DecodeOptions) -> Result<(Bytes, Option<String>), Error> {
+    let key = cache_key(uri, options);
+    if let Some(cached) = options.cache_ttl.and_then(|ttl| cache::load(&key, ttl)) {
+        return Ok(cached);
+    }
+    if options.cached_only {
+        return Err(Error::fetch(uri, "Not in the cache and previews do not download without --preview-fetch"));
+    }
+    let bytes = get();
+    if let (Ok((b, media_type)), Some(_)) = (&bytes, options.cache_ttl) {
+        cache::store(&key, b, media_type.as_deref());
+    }
+    return bytes;
+}
+
+fn get_bytes_from_data_uri(uri: &str) -> Result<(Bytes, Option<String>), Error> {
+    let (header, payload) = match uri.strip_prefix("data:").and_then(|u| u.split_once(',')) {
+        Some(parts) => parts,
+        None => return Err(Error::fetch("data URI", "Malformed data URI"))
+    };
+    if !header.ends_with(";base64") {
+        return Err(Error::fetch("data URI", format!("Only base64 encoded data URIs are supported: {}", header)));
+    }
+    let media_type = header.strip_suffix(";base64").filter(|t| !t.is_empty()).map(str::to_owned);
+    return match STANDARD.decode(payload.trim()) {
+        Ok(bytes) => Ok((Bytes::from(bytes), media_type)),
+        Err(e) => Err(Error::fetch("data URI", e))
+    }
+}
+
+fn get_bytes_from_stdin() -> Result<Bytes, Error> {
+    let mut bytes = Vec::new();
+    return match std::io::stdin().lock().read_to_end(&mut bytes) {
+        Ok(_) => Ok(Bytes::from(bytes)),
+        Err(e) => Err(Error::fetch("standard input", e))
+    }
+}
+
+// The clipboard holds raw pixels, which are encoded as PNG so that they can
+// be handled like any other input.
+#[cfg(feature = "clipboard")]
+fn get_bytes_from_clipboard() -> Result<Bytes, Error> {
+    let image = arboard::Clipboard::new()
+        .and_then(|mut c| c.get_image())
+        .map_err(|e| Error::fetch("clipboard", e))?;
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(&image.bytes, image.width as u32, image.height as u32, image::ExtendedColorType::Rgba8)
+        .map_err(|e| Error::fetch("clipboard", e))?;
+    return Ok(Bytes::from(png));
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn get_bytes_from_clipboard() -> Result<Bytes, Error> {
+    return Err(Error::fetch("clipboard", "Clipboard input requires the `clipboard` feature"));
+}
+
+fn get_bytes_from_file(path: &String, options: &DecodeOptions) -> Result<Bytes, Error> {
+    // Checks the size up front rather than reading an oversized file first.
+    if let Ok(metadata) = std::fs::metadata(path) {
+        within_max_bytes(metadata.len(), path, options)?;
+    }
+    return match std::fs::read(path) {
+        Ok(bytes) => Ok(Bytes::from(bytes)),
+        Err(e) => Err(Error::fetch(path, e))
+    }
+}
+
+// The file is read up front so that reading and decoding are timed separately.
+// The format is taken from the extension, or guessed if it is not known.
+fn get_image_from_file(path: &String, options: &DecodeOptions) -> Result<DynamicImage, Error> {
+    let bytes = timing::measure(Stage::Fetch, || get_bytes_from_file(path, options))?;
+    return timing::measure(Stage::Decode, || decode(bytes, ImageFormat::from_path(path).map_or(Format::Guessed(None), Format::Known), path, options));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svg_extensions_ignore_case() {
+        assert!(is_svg_path("logo.svg"));
+        assert!(is_svg_path("IMAGE.SVG"));
+        assert!(is_svg_path("dir/Icon.Svg"));
+        assert!(!is_svg_path("photo.png"));
+        assert!(!is_svg_path("svg"));
+    }
+}
@@ -0,0 +1,19 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use image::DynamicImage;
+use image::ImageError;
+use image::ImageFormat;
+use std::io::Cursor;
+
+/// Encodes `image` as PNG wrapped in an OSC 1337 `File=inline=1` sequence,
+/// scaled by the terminal to `columns` cells wide.
+pub fn encode(image: &DynamicImage, columns: usize) -> Result<String, ImageError> {
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageFormat::Png)?;
+    let png = png.into_inner();
+    return Ok(format!(
+        "\x1b]1337;File=inline=1;size={};width={};preserveAspectRatio=1:{}\x07",
+        png.len(),
+        columns,
+        STANDARD.encode(&png)));
+}
@@ -0,0 +1,81 @@
+use image::{DynamicImage, ImageBuffer, Pixel};
+
+/// Square convolution kernel of `--kernel`, weights row by row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kernel {
+    size: usize,
+    weights: Vec<f64>,
+}
+
+/// Parses comma or space separated weights of a kernel of 3x3, 5x5 or any
+/// other odd square size. Kernels with weights that do not sum to 0 are
+/// divided by their sum, so that `1,1,1,1,1,1,1,1,1` blurs without
+/// brightening.
+pub fn parse(s: &str) -> Result<Kernel, String> {
+    let weights: Vec<f64> = s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.parse::<f64>().ok().filter(|w| w.is_finite()).ok_or_else(|| format!("Expected a number: {}", w)))
+        .collect::<Result<_, _>>()?;
+    let size = (weights.len() as f64).sqrt().round() as usize;
+    if size * size != weights.len() || size.is_multiple_of(2) {
+        return Err(format!("Expected an odd square number of weights like 9 or 25, found {}", weights.len()));
+    }
+    let sum: f64 = weights.iter().sum();
+    let weights = if sum.abs() > f64::EPSILON { weights.iter().map(|w| w / sum).collect() } else { weights };
+    return Ok(Kernel { size, weights });
+}
+
+// Convolves the colour channels of `buf`, repeating the pixels at its edges
+// beyond them, and keeps the alpha channel of RGBA images.
+fn convolve<P: Pixel<Subpixel = u8>>(buf: &ImageBuffer<P, Vec<u8>>, kernel: &Kernel) -> ImageBuffer<P, Vec<u8>> {
+    let (width, height) = (buf.width() as i64, buf.height() as i64);
+    let radius = (kernel.size / 2) as i64;
+    let mut out = buf.clone();
+    for (x, y, pixel) in out.enumerate_pixels_mut() {
+        let mut sum = [0.0; 3];
+        for (i, weight) in kernel.weights.iter().enumerate() {
+            let nx = (x as i64 + (i % kernel.size) as i64 - radius).clamp(0, width - 1);
+            let ny = (y as i64 + (i / kernel.size) as i64 - radius).clamp(0, height - 1);
+            let neighbour = buf.get_pixel(nx as u32, ny as u32).channels();
+            for c in 0 .. 3 {
+                sum[c] += weight * neighbour[c] as f64;
+            }
+        }
+        for (c, s) in pixel.channels_mut().iter_mut().zip(sum) {
+            *c = s.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    return out;
+}
+
+/// Copy of an 8 bit RGB or RGBA `image` convolved with `kernel`.
+pub fn apply(image: &DynamicImage, kernel: &Kernel) -> DynamicImage {
+    return match image {
+        DynamicImage::ImageRgb8(buf) => DynamicImage::ImageRgb8(convolve(buf, kernel)),
+        DynamicImage::ImageRgba8(buf) => DynamicImage::ImageRgba8(convolve(buf, kernel)),
+        i => i.clone()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn parses_odd_squares() {
+        assert_eq!(parse("0,-1,0,-1,5,-1,0,-1,0").unwrap().size, 3);
+        assert_eq!(parse("1 1 1  1 1 1  1 1 1").unwrap().weights, vec![1.0 / 9.0; 9]);
+        assert_eq!(parse("2").unwrap().weights, vec![1.0]);
+        assert!(parse("1,2,3,4").is_err());
+        assert!(parse("1,2,x").is_err());
+    }
+
+    #[test]
+    fn box_blur_averages_neighbours() {
+        let buf = RgbImage::from_fn(3, 3, |x, y| if (x, y) == (1, 1) { Rgb([90, 90, 90]) } else { Rgb([0, 0, 0]) });
+        let blurred = convolve(&buf, &parse("1,1,1,1,1,1,1,1,1").unwrap());
+        assert_eq!(blurred.get_pixel(1, 1).0, [10, 10, 10]);
+        assert_eq!(blurred.get_pixel(0, 0).0, [10, 10, 10]);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::render::visible_width;
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Blank rows above and below the output and blank columns left and right of it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Padding {
+    top: usize,
+    right: usize,
+    bottom: usize,
+    left: usize,
+}
+
+/// Parses padding given like in CSS, as `all`, `vertical,horizontal` or
+/// `top,right,bottom,left`.
+pub fn parse_padding(s: &str) -> Result<Padding, String> {
+    let values = s.split(',')
+        .map(|v| v.trim().parse::<usize>().map_err(|_| format!("Expected a number of cells: {}", v)))
+        .collect::<Result<Vec<_>, _>>()?;
+    return match values[..] {
+        [all] => Ok(Padding { top: all, right: all, bottom: all, left: all }),
+        [vertical, horizontal] => Ok(Padding { top: vertical, right: horizontal, bottom: vertical, left: horizontal }),
+        [top, right, bottom, left] => Ok(Padding { top, right, bottom, left }),
+        _ => Err(format!("Expected T,R,B,L: {}", s))
+    }
+}
+
+/// Indents `lines` to `align` them within `width` terminal columns less the
+/// horizontal padding, and adds the vertical padding as blank lines. With
+/// `forward` the indentation moves the cursor instead of printing spaces,
+/// keeping whatever is already on screen, like the borders of a pane.
+pub fn place(lines: Vec<String>, width: usize, align: Align, padding: Padding, forward: bool) -> Vec<String> {
+    let block = lines.iter().map(|l| visible_width(l)).max().unwrap_or(0);
+    let room = width.saturating_sub(padding.left + padding.right).saturating_sub(block);
+    let indent = padding.left + match align {
+        Align::Left => 0,
+        Align::Center => room / 2,
+        Align::Right => room,
+    };
+    let prefix = match indent {
+        0 => String::new(),
+        n if forward => format!("\x1b[{}C", n),
+        n => " ".repeat(n)
+    };
+    let mut placed = vec![String::new(); padding.top];
+    placed.extend(lines.into_iter().map(|line| format!("{}{}", prefix, line)));
+    placed.extend(vec![String::new(); padding.bottom]);
+    return placed;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Border {
+    None,
+    Ascii,
+    Rounded,
+    Double,
+}
+
+impl Border {
+    // Corners top left, top right, bottom left, bottom right, then the
+    // horizontal and vertical edges.
+    fn glyphs(&self) -> Option<[char; 6]> {
+        return match self {
+            Border::None => None,
+            Border::Ascii => Some(['+', '+', '+', '+', '-', '|']),
+            Border::Rounded => Some(['╭', '╮', '╰', '╯', '─', '│']),
+            Border::Double => Some(['╔', '╗', '╚', '╝', '═', '║']),
+        }
+    }
+}
+
+/// Draws `border` around `lines`, padding them to the widest, with `title`
+/// in the top edge, shortened to fit.
+pub fn frame(lines: Vec<String>, border: Border, title: Option<&str>) -> Vec<String> {
+    let Some([top_left, top_right, bottom_left, bottom_right, horizontal, vertical]) = border.glyphs() else {
+        return lines;
+    };
+    let block = lines.iter().map(|l| visible_width(l)).max().unwrap_or(0);
+    let label = match title {
+        Some(title) if block >= 3 => format!(" {} ", title.chars().take(block - 3).collect::<String>()),
+        _ => String::new()
+    };
+    let lead = if label.is_empty() { 0 } else { 1 };
+    let rest = block - lead - label.chars().count();
+    let mut framed = Vec::with_capacity(lines.len() + 2);
+    framed.push(format!("{}{}{}{}{}", top_left, horizontal.to_string().repeat(lead), label, horizontal.to_string().repeat(rest), top_right));
+    for line in lines {
+        let fill = " ".repeat(block - visible_width(&line));
+        framed.push(format!("{}{}{}{}", vertical, line, fill, vertical));
+    }
+    framed.push(format!("{}{}{}", bottom_left, horizontal.to_string().repeat(block), bottom_right));
+    return framed;
+}
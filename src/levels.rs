@@ -0,0 +1,107 @@
+use crate::histogram;
+use image::DynamicImage;
+
+// Share of the darkest and of the brightest samples --auto-contrast clips,
+// so that a few hot pixels or specks of dust do not hold the range open.
+const CLIP: f64 = 0.005;
+
+// Counts of the red, green and blue samples of the opaque pixels together,
+// so that the curves built from them treat the channels alike and keep hues.
+fn samples(image: &DynamicImage) -> [u64; 256] {
+    let counts = histogram::compute(image);
+    return std::array::from_fn(|v| counts[0][v] + counts[1][v] + counts[2][v]);
+}
+
+/// Curve stretching the samples between the darkest and the brightest,
+/// leaving out `CLIP` at either end, to the full range.
+pub fn auto_contrast(counts: &[u64; 256]) -> [u8; 256] {
+    let total: u64 = counts.iter().sum();
+    let clipped = (total as f64 * CLIP) as u64;
+    let mut below = 0;
+    let low = counts.iter().position(|c| { below += c; below > clipped }).unwrap_or(0);
+    let mut above = 0;
+    let high = 255 - counts.iter().rev().position(|c| { above += c; above > clipped }).unwrap_or(0);
+    if high <= low {
+        return std::array::from_fn(|v| v as u8);
+    }
+    return std::array::from_fn(|v| ((v as f64 - low as f64) / (high - low) as f64 * 255.0).round().clamp(0.0, 255.0) as u8);
+}
+
+/// Curve spreading the samples evenly over the full range, through their
+/// cumulative distribution.
+pub fn equalize(counts: &[u64; 256]) -> [u8; 256] {
+    let total: u64 = counts.iter().sum();
+    let first = counts.iter().copied().find(|c| *c > 0).unwrap_or(0);
+    if total == first {
+        return std::array::from_fn(|v| v as u8);
+    }
+    let mut cumulative = 0;
+    return counts.map(|c| {
+        cumulative += c;
+        ((cumulative.saturating_sub(first)) as f64 / (total - first) as f64 * 255.0).round() as u8
+    });
+}
+
+// Maps the colour channels of an 8 bit RGB or RGBA `image` through `curve`.
+fn map(mut image: DynamicImage, curve: &[u8; 256]) -> DynamicImage {
+    let channels = image.color().channel_count() as usize;
+    let samples: &mut [u8] = match &mut image {
+        DynamicImage::ImageRgb8(buf) => buf,
+        DynamicImage::ImageRgba8(buf) => buf,
+        _ => return image
+    };
+    for pixel in samples.chunks_mut(channels) {
+        for sample in &mut pixel[.. 3] {
+            *sample = curve[*sample as usize];
+        }
+    }
+    return image;
+}
+
+/// Copy of an 8 bit RGB or RGBA `image` with --auto-contrast and then
+/// --equalize applied. Transparent pixels do not count towards the curves.
+pub fn apply(image: &DynamicImage, auto_contrast: bool, equalize: bool) -> DynamicImage {
+    let mut image = image.clone();
+    if auto_contrast {
+        let curve = self::auto_contrast(&samples(&image));
+        image = map(image, &curve);
+    }
+    if equalize {
+        let curve = self::equalize(&samples(&image));
+        image = map(image, &curve);
+    }
+    return image;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auto_contrast_stretches_the_range() {
+        let mut counts = [0; 256];
+        counts[64] = 1000;
+        counts[192] = 1000;
+        counts[250] = 1;
+        let curve = auto_contrast(&counts);
+        assert_eq!((curve[64], curve[128], curve[192], curve[250]), (0, 128, 255, 255));
+    }
+
+    #[test]
+    fn equalize_spreads_the_samples() {
+        let mut counts = [0; 256];
+        counts[10] = 1;
+        counts[11] = 1;
+        counts[12] = 2;
+        let curve = equalize(&counts);
+        assert_eq!((curve[10], curve[11], curve[12]), (0, 85, 255));
+    }
+
+    #[test]
+    fn flat_images_are_kept() {
+        let mut counts = [0; 256];
+        counts[100] = 50;
+        assert_eq!(auto_contrast(&counts)[100], 100);
+        assert_eq!(equalize(&counts)[100], 100);
+    }
+}
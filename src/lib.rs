@@ -0,0 +1,1978 @@
+#![allow(clippy::needless_return)]
+// WebAssembly builds only reach the renderer, through the bindings in wasm.rs.
+#![cfg_attr(target_arch = "wasm32", allow(dead_code, unused_imports))]
+
+mod aggregate;
+mod animation;
+mod bench;
+mod cache;
+mod caption;
+mod cell_optimizer;
+#[cfg(feature = "cloud")]
+mod cloud;
+mod container;
+mod crop;
+mod dominant;
+mod edges;
+mod effects;
+mod error;
+mod export;
+mod ffi;
+mod ftp;
+#[cfg(feature = "heic")]
+mod heif;
+mod histogram;
+#[cfg(feature = "icc")]
+mod icc;
+mod http;
+mod input;
+mod iterm2;
+mod kernel;
+mod layout;
+mod levels;
+mod lut;
+mod metrics;
+mod montage;
+#[cfg(not(target_arch = "wasm32"))]
+mod pager;
+mod page;
+mod palette;
+#[cfg(feature = "pdf")]
+mod pdf;
+mod perceptual;
+mod random;
+#[cfg(feature = "raw")]
+mod raw;
+mod redraw;
+pub mod render;
+// Public for benches/rowsum.rs only.
+#[doc(hidden)]
+pub mod rowsum;
+#[cfg(not(target_arch = "wasm32"))]
+mod screen;
+mod shade;
+mod sharpen;
+mod sixel;
+#[cfg(not(target_arch = "wasm32"))]
+mod slideshow;
+mod ssh;
+#[cfg(feature = "svg")]
+mod svg;
+mod theme;
+mod threshold;
+mod timing;
+mod tmux;
+mod tonemap;
+#[cfg(feature = "video")]
+mod video;
+#[cfg(not(target_arch = "wasm32"))]
+mod viewer;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+#[cfg(not(target_arch = "wasm32"))]
+mod watch;
+
+use aggregate::{Aggregate, AverageSpace, Collected, Coverage, LinearRGBSum, OklabSum};
+use ansi_term::Colour;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use image::{DynamicImage, GenericImageView};
+use image::Frame;
+use image::imageops;
+use image::imageops::FilterType;
+use image::ImageBuffer;
+use image::{Rgb, RgbImage, Rgba, RgbaImage};
+use image::Pixel;
+use http::HttpOptions;
+use error::Error;
+use input::{get_image, DecodeOptions};
+use metrics::Metric;
+use timing::Stage;
+use palette::{ColorChoice, ColorDepth};
+use render::{Fill, Paint};
+pub use render::Renderer;
+use rowsum::ChannelSum;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::ops::Deref;
+use std::str;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub struct RGBSum {
+    r: u64,
+    g: u64,
+    b: u64,
+}
+
+impl RGBSum {
+    fn zero() -> RGBSum {
+        RGBSum { r: 0, g: 0, b: 0 }
+    }
+
+    #[allow(dead_code)]
+    fn set_zero(&mut self) -> &RGBSum {
+        self.r = 0;
+        self.g = 0;
+        self.b = 0;
+        return self;
+    }
+
+    #[allow(dead_code)]
+    fn add(&mut self, other: &RGBSum) {
+        self.r += other.r;
+        self.g += other.g;
+        self.b += other.b;
+    }
+
+    fn div(&mut self, n: u64) {
+        self.r /= n;
+        self.g /= n;
+        self.b /= n;
+    }
+}
+
+trait Div<T> {
+   fn div_inplace(&mut self, divisor: T);
+}
+
+trait IsSame {
+    fn is_same(&self, color: &Colour) -> bool;
+}
+
+impl Div<u64> for RGBSum {
+    fn div_inplace(&mut self, divisor: u64) {
+        self.div(divisor);
+    }
+}
+
+impl IsSame for RGBSum {
+    fn is_same(&self, color: &Colour) -> bool {
+        return match color {
+            Colour::RGB(r, g, b ) => self.r == u64::from(*r) && self.g == u64::from(*g) && self.b == u64::from(*b),
+            _ => false
+        }
+    }
+}
+
+trait ToColour {
+    fn to_colour(&self) -> Colour;
+}
+
+impl ToColour for RGBSum {
+    fn to_colour(&self) -> Colour {
+        return Colour::RGB(self.r as u8, self.g as u8, self.b as u8);
+    }
+}
+
+impl ToColour for Colour {
+    fn to_colour(&self) -> Colour {
+        return *self;
+    }
+}
+
+impl IsSame for Colour {
+    fn is_same(&self, color: &Colour) -> bool {
+        return self == color;
+    }
+}
+
+trait Aggregator<P>: Div<u64> {
+    fn aggregate(&mut self, p: &P);
+
+    /// Aggregates a run of pixels given as their interleaved subpixels.
+    fn aggregate_run(&mut self, subpixels: &[P::Subpixel]) where P: Pixel, P::Subpixel: ChannelSum {
+        for p in subpixels.chunks_exact(P::CHANNEL_COUNT as usize) {
+            self.aggregate(P::from_slice(p));
+        }
+    }
+}
+
+impl RGBSum {
+    fn add_sums(&mut self, sums: [u64; 3]) {
+        self.r += sums[0];
+        self.g += sums[1];
+        self.b += sums[2];
+    }
+}
+
+impl <U: Into<u64> + Copy> Aggregator<Rgb<U>> for RGBSum {
+    fn aggregate(&mut self, p: &Rgb<U>) {
+        self.r += p[0].into();
+        self.g += p[1].into();
+        self.b += p[2].into();
+    }
+
+    fn aggregate_run(&mut self, subpixels: &[<Rgb<U> as Pixel>::Subpixel]) where Rgb<U>: Pixel, <Rgb<U> as Pixel>::Subpixel: ChannelSum {
+        self.add_sums(ChannelSum::sum_channels(subpixels, 3));
+    }
+}
+
+impl <U: Into<u64> + Copy> Aggregator<Rgba<U>> for RGBSum {
+    fn aggregate(&mut self, p: &Rgba<U>) {
+        self.r += p[0].into();
+        self.g += p[1].into();
+        self.b += p[2].into();
+    }
+
+    fn aggregate_run(&mut self, subpixels: &[<Rgba<U> as Pixel>::Subpixel]) where Rgba<U>: Pixel, <Rgba<U> as Pixel>::Subpixel: ChannelSum {
+        self.add_sums(ChannelSum::sum_channels(subpixels, 4));
+    }
+}
+
+impl Clone for RGBSum {
+    fn clone(&self) -> Self {
+        return RGBSum {
+            r: self.r,
+            g: self.g,
+            b: self.b
+        }
+    }
+}
+
+fn sum_chunks_inplace<P: Pixel<Subpixel: ChannelSum>, Agg: Aggregator<P>, C: Deref<Target = [P::Subpixel]>>(
+    image: &ImageBuffer<P, C>,
+    chunk_width: u32,
+    row: u32,
+    target: &mut [Agg]
+) {
+    let channels = P::CHANNEL_COUNT as usize;
+    let row_length = image.width() as usize * channels;
+    let subpixels = &image.as_raw()[row as usize * row_length .. (row as usize + 1) * row_length];
+    // The last chunk is narrower if the width is not a multiple of the chunk width.
+    for (agg, run) in target.iter_mut().zip(subpixels.chunks(chunk_width as usize * channels)) {
+        agg.aggregate_run(run);
+    }
+}
+
+/// Chunks sampled from an image, `n_x` wide and `n_y` high, row by row.
+pub struct Grid<T> {
+    n_x: usize,
+    n_y: usize,
+    cells: Vec<T>
+}
+
+impl <T> Grid<T> {
+    /// Grid of `cells` row by row, or `None` unless there are `n_x` x `n_y` of them.
+    pub fn new(n_x: usize, n_y: usize, cells: Vec<T>) -> Option<Grid<T>> {
+        return (n_x.checked_mul(n_y) == Some(cells.len())).then_some(Grid { n_x, n_y, cells });
+    }
+
+    /// Number of chunks per row.
+    pub fn width(&self) -> usize {
+        return self.n_x;
+    }
+
+    /// Number of rows.
+    pub fn height(&self) -> usize {
+        return self.n_y;
+    }
+
+    /// Chunks of row `y`.
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.n_x .. y * self.n_x + self.n_x]
+    }
+
+    fn map<U, F: Fn(&T) -> U>(&self, f: F) -> Grid<U> {
+        return Grid { n_x: self.n_x, n_y: self.n_y, cells: self.cells.iter().map(f).collect() };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    /// Runs of `$` painted with the chunk colour
+    Runs,
+    /// `▀` with the upper chunk as foreground and the lower chunk as background
+    HalfBlock,
+    /// Braille glyphs covering 2x4 chunks, painted with their average colour
+    Braille,
+    /// DEC sixel graphics with one block of pixels per chunk
+    Sixel,
+    /// Full resolution image through the iTerm2 inline image protocol
+    Iterm2,
+    /// Uncoloured characters picked from `--charset` by chunk luminance
+    Ascii,
+    /// Line art of the edges in the image luminance, in braille or box drawing characters covering 2x4 chunks
+    Edges,
+    /// `░▒▓█` shade blocks mixing two colours per chunk, approximating the colours a 16 or 256 colour terminal lacks
+    Shades,
+    /// Block glyphs picked from `--glyphs` per cell of 2x6 chunks, with the colours that draw the chunks best
+    Blocks,
+    /// Black and white half blocks, white where the chunk luminance reaches `--level`, dithered with `--dither`
+    Threshold,
+}
+
+impl Mode {
+    /// Number of chunks drawn per terminal cell horizontally and vertically.
+    fn chunks_per_cell(&self) -> (usize, usize) {
+        return match self {
+            Mode::HalfBlock | Mode::Threshold => (1, 2),
+            Mode::Braille | Mode::Edges => (2, 4),
+            Mode::Blocks => cell_optimizer::CELL,
+            _ => (1, 1)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Output {
+    /// Text with colour escapes drawn in the chosen mode
+    Ansi,
+    /// The sampled chunk colours as JSON
+    Json,
+    /// An HTML `<pre>` block reproducing the text of a character mode
+    Html,
+    /// An SVG mosaic with one rectangle per chunk
+    Svg,
+    /// The sampled chunk colours as comma separated row,col,r,g,b records
+    Csv,
+    /// The sampled chunk colours as tab separated row, col, r, g, b records
+    Tsv,
+    /// The sampled chunk colours as a NumPy array of shape rows x columns x 3 in bytes
+    Npy,
+}
+
+impl Output {
+    /// Whether the output describes the chunk grid rather than text drawn in a mode.
+    fn is_grid(&self) -> bool {
+        return matches!(self, Output::Json | Output::Svg | Output::Csv | Output::Tsv | Output::Npy);
+    }
+
+    /// Whether the output is binary data written as the image is rendered,
+    /// rather than text lines.
+    fn is_binary(&self) -> bool {
+        return *self == Output::Npy;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Filter {
+    /// Average of all pixels in a chunk
+    Box,
+    Nearest,
+    Triangle,
+    Catmullrom,
+    Lanczos3,
+}
+
+impl Filter {
+    fn filter_type(&self) -> Option<FilterType> {
+        return match self {
+            Filter::Box => None,
+            Filter::Nearest => Some(FilterType::Nearest),
+            Filter::Triangle => Some(FilterType::Triangle),
+            Filter::Catmullrom => Some(FilterType::CatmullRom),
+            Filter::Lanczos3 => Some(FilterType::Lanczos3),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Frames {
+    /// Evenly spaced frames side by side, as a contact sheet
+    Strip,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Fit {
+    /// Show the whole image as large as it fits, keeping its aspect ratio
+    Contain,
+    /// Fill the whole box keeping the aspect ratio, cropping the image around its centre
+    Cover,
+    /// Fill the whole box, distorting the aspect ratio of the image
+    Stretch,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum Command {
+    /// Print the dominant colours of an image as swatches with their hex codes
+    Palette {
+        image: String,
+
+        /// Number of colours to extract
+        #[arg(short = 'n', long, default_value_t = 8)]
+        count: usize,
+
+        /// Format of the extracted colours, for theming tools
+        #[arg(long, value_enum, default_value_t = export::PaletteFormat::Swatches)]
+        format: export::PaletteFormat,
+    },
+    /// Print a swatch and the hex and RGB values of the average colour of an image, taken in linear light
+    Avg {
+        image: String,
+    },
+    /// Draw bar charts of the red, green, blue and luminance histograms of an image
+    Histogram {
+        image: String,
+
+        /// Number of bars per histogram
+        #[arg(long, default_value_t = 64)]
+        bins: usize,
+
+        /// Height of each histogram in rows
+        #[arg(long, default_value_t = 8)]
+        height: usize,
+    },
+    /// Explore an image interactively, panning with the arrow keys and zooming with + and -
+    View {
+        image: String,
+    },
+    /// Show two images side by side through the same grid, or highlight the cells where they differ
+    Diff {
+        first: String,
+        second: String,
+
+        /// Show the first image dimmed, with the cells that differ from the second in red
+        #[arg(long)]
+        highlight: bool,
+
+        /// Distance between the RGB colours of two cells above which they differ
+        #[arg(long, default_value_t = 32.0, value_parser = parse_non_negative)]
+        threshold: f64,
+    },
+    /// Print how similar two images are, sampled into the chunks of the arguments
+    Compare {
+        first: String,
+        second: String,
+
+        #[arg(long, value_enum, default_value_t = Metric::Ssim)]
+        metric: Metric,
+
+        /// Exit with code 1 if the images are less similar than this
+        #[arg(long)]
+        threshold: Option<f64>,
+    },
+    /// Time decoding, aggregation, rendering and printing across image sizes and chunk sizes
+    Bench {
+        /// Image resized to every size, a synthetic one if omitted
+        image: Option<String>,
+
+        /// Image sizes as WIDTHxHEIGHT, separated by commas
+        #[arg(long, value_delimiter = ',', default_value = "640x480,1920x1080,3840x2160", value_parser = bench::parse_size)]
+        sizes: Vec<(u32, u32)>,
+
+        /// Chunk sizes as XxY, separated by commas
+        #[arg(long, value_delimiter = ',', default_value = "4x8,10x20,20x40", value_parser = bench::parse_size)]
+        chunks: Vec<(u32, u32)>,
+
+        /// Number of times each combination is timed, the mean is reported
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+        iterations: u32,
+    },
+    /// Print a completion script for a shell to standard output
+    Completions {
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about, long_about = None, after_help = error::EXIT_CODES)]
+struct Args {
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Paths or URLs of the images to render, side by side if there is more than one. `-` reads standard input
+    images: Vec<String>,
+
+    /// Same as the positional images, kept for compatibility
+    #[arg(short, long)]
+    image_url: Vec<String>,
+
+    /// Render the image on the system clipboard, after any other images
+    #[arg(long)]
+    clipboard: bool,
+
+    /// Width in pixels of the chunk sampled for every cell, ignored when the size is given by --width, --height or --scale
+    #[arg(short, long, default_value_t = 20, value_parser = parse_chunk_size)]
+    x_chunks: usize,
+
+    /// Height in pixels of the chunk sampled for every cell, ignored when the size is given by --width, --height or --scale
+    #[arg(short, long, default_value_t = 40, value_parser = parse_chunk_size)]
+    y_chunks: usize,
+
+    #[arg(short, long, value_enum, default_value_t = Mode::Runs)]
+    mode: Mode,
+
+    /// Format of the output. The json and svg formats describe the chunk grid and ignore the mode
+    #[arg(long, value_enum, default_value_t = Output::Ansi)]
+    output: Output,
+
+    /// Also write the chunk colours to this image file, one pixel per chunk
+    #[arg(long)]
+    save_thumbnail: Option<std::path::PathBuf>,
+
+    /// Render only the region x,y,w,h of the image, each in pixels or as a percentage like 50%
+    #[arg(long, value_parser = crop::parse)]
+    crop: Option<crop::Crop>,
+
+    /// Resampling filter used to reduce each chunk to a single colour
+    #[arg(long, value_enum, default_value_t = Filter::Box)]
+    filter: Filter,
+
+    /// Statistic reducing the pixels of a chunk to a single colour
+    #[arg(long, value_enum, default_value_t = Aggregate::Mean)]
+    aggregate: Aggregate,
+
+    /// Colour space in which the mean aggregate averages the chunks
+    #[arg(long, value_enum, default_value_t = AverageSpace::Srgb)]
+    average_space: AverageSpace,
+
+    /// Average chunks in linear light rather than on gamma encoded sRGB values, same as --average-space linear
+    #[arg(long, conflicts_with = "average_space")]
+    linear: bool,
+
+    /// Colour as #rrggbb that transparent pixels are composited over, black if omitted.
+    /// Without it, fully transparent cells keep the terminal background in the half-block mode
+    #[arg(long, value_parser = palette::parse_hex)]
+    background: Option<Colour>,
+
+    /// Factor scaling the brightness of the chunk colours
+    #[arg(long, default_value_t = 1.0, value_parser = parse_non_negative)]
+    brightness: f64,
+
+    /// Factor scaling the contrast of the chunk colours around mid gray
+    #[arg(long, default_value_t = 1.0, value_parser = parse_non_negative)]
+    contrast: f64,
+
+    /// Factor scaling the saturation of the chunk colours, 0 is gray
+    #[arg(long, default_value_t = 1.0, value_parser = parse_non_negative)]
+    saturation: f64,
+
+    /// Stretch the levels of the image to the full range before sampling, after --kernel, clipping the darkest and brightest 0.5%
+    #[arg(long)]
+    auto_contrast: bool,
+
+    /// Spread the levels of the image evenly over the full range before sampling, after --auto-contrast
+    #[arg(long)]
+    equalize: bool,
+
+    /// Weights of a square convolution kernel applied to the image before sampling, row by row like 0,-1,0,-1,5,-1,0,-1,0
+    #[arg(long, value_parser = kernel::parse, allow_hyphen_values = true)]
+    kernel: Option<kernel::Kernel>,
+
+    /// Strength of an unsharp mask, like 1.0, that keeps text and edges crisp when many pixels are averaged into a chunk
+    #[arg(long, default_value_t = 0.0, value_parser = parse_non_negative)]
+    sharpen: f64,
+
+    /// Whether --sharpen applies to the image before sampling or to the sampled chunk colours
+    #[arg(long, value_enum, default_value_t = sharpen::Stage::Before)]
+    sharpen_stage: sharpen::Stage,
+
+    /// Grade the chunk colours with the 3D or 1D lookup table of a .cube file, before any other colour changes
+    #[arg(long, value_parser = lut::load)]
+    lut: Option<std::sync::Arc<lut::Lut>>,
+
+    /// Effect applied to the chunk colours, repeat to apply several in order
+    #[arg(long, value_enum)]
+    filter_fx: Vec<effects::Effect>,
+
+    /// Show the chunk colours as seen with a colour vision deficiency
+    #[arg(long, value_enum)]
+    simulate: Option<effects::Deficiency>,
+
+    /// Snap every chunk colour to the nearest colour of a terminal theme
+    #[arg(long, value_enum)]
+    theme: Option<theme::Theme>,
+
+    /// Reduce the chunk colours to at most this many, found by median cut, for flat stylized output with shorter escapes
+    #[arg(long, visible_alias = "max-colors", value_parser = clap::value_parser!(u32).range(1..))]
+    posterize: Option<u32>,
+
+    /// Colour difference by which chunk colours are snapped to the 256 or 16 colour palette and to --theme
+    #[arg(long, value_enum, default_value_t = perceptual::Distance::Rgb)]
+    color_distance: perceptual::Distance,
+
+    /// Curve mapping the linear light of 16 bit and HDR images to the displayable range
+    #[arg(long, value_enum, default_value_t = tonemap::ToneMap::Clamp)]
+    tonemap: tonemap::ToneMap,
+
+    /// Dither chunk colours when rendering with 256 or 16 colours or in the threshold mode, by Floyd-Steinberg error diffusion (fs) if given without a value
+    #[arg(long, value_enum, default_value_t = palette::Dither::None, num_args = 0..=1, default_missing_value = "fs")]
+    dither: palette::Dither,
+
+    /// Seed of the random numbers behind --shuffle, so that they repeat from run to run
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Colour depth of the escapes emitted by the character renderers, detected from the environment if omitted
+    #[arg(long, value_enum)]
+    colors: Option<ColorDepth>,
+
+    /// Whether to emit colour escapes at all, by default only when writing to a terminal
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Emit colour escapes even when writing to a file or pipe, same as --color always
+    #[arg(long, conflicts_with = "color")]
+    force_color: bool,
+
+    /// Write the output, escapes included, to this file instead of standard output
+    #[arg(short, long)]
+    output_file: Option<std::path::PathBuf>,
+
+    /// Character painted for every chunk in the runs mode
+    #[arg(long, conflicts_with = "charset")]
+    char: Option<char>,
+
+    /// Characters ordered from dark to bright, picked by chunk luminance in the runs and ascii modes
+    #[arg(long, value_parser = parse_charset)]
+    charset: Option<String>,
+
+    /// Block glyphs the blocks mode picks from
+    #[arg(long, value_enum, default_value_t = cell_optimizer::Glyphs::Quadrants)]
+    glyphs: cell_optimizer::Glyphs,
+
+    /// Characters the edges mode draws edges with
+    #[arg(long, value_enum, default_value_t = edges::Glyphs::Braille)]
+    edge_glyphs: edges::Glyphs,
+
+    /// Gradient of the luminance from which the edges mode draws an edge, where a step from black to white is 1
+    #[arg(long, default_value_t = 0.2, value_parser = parse_non_negative)]
+    edge_threshold: f64,
+
+    /// Paint the edges with the average colour of their cells instead of the terminal foreground
+    #[arg(long)]
+    edge_color: bool,
+
+    /// Luminance from 0 to 1 from which the threshold mode draws a chunk white
+    #[arg(long, default_value_t = 0.5, value_parser = parse_level)]
+    level: f64,
+
+    /// Whether the runs mode colours the glyphs or the cell background
+    #[arg(long, value_enum, default_value_t = Paint::Fg)]
+    paint: Paint,
+
+    /// Play animated GIF, APNG and WebP images in a loop until interrupted
+    #[arg(long, conflicts_with = "frame")]
+    animate: bool,
+
+    /// Frames per second of --animate, instead of the delays stored in the image
+    #[arg(long, value_parser = parse_positive, conflicts_with = "speed")]
+    fps: Option<f64>,
+
+    /// Factor like 2x by which --animate plays faster than the delays stored in the image
+    #[arg(long, default_value = "1x", value_parser = parse_speed)]
+    speed: f64,
+
+    /// Number of times --animate plays the animation, forever if omitted
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    loops: Option<u32>,
+
+    /// Render only the frame with this zero-based index of an animated image
+    #[arg(long)]
+    frame: Option<usize>,
+
+    /// Render several frames of an animated image at once instead of animating it
+    #[arg(long, value_enum, conflicts_with_all = ["animate", "frame"])]
+    frames: Option<Frames>,
+
+    /// Number of frames shown by --frames
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(1..))]
+    frame_count: u32,
+
+    /// Position of the frame rendered from video input, as [[HH:]MM:]SS
+    #[arg(long, value_parser = parse_timestamp)]
+    timestamp: Option<Duration>,
+
+    /// Number of the page rendered from PDF documents and multi-page TIFF files, starting at 1
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    page: u32,
+
+    /// Width in pixels of the size picked from icons with several, or best for the largest
+    #[arg(long, default_value = "best", value_parser = parse_icon_size)]
+    icon_size: u32,
+
+    /// Number of images per row when rendering several images, all in one row if omitted
+    #[arg(long)]
+    grid_columns: Option<usize>,
+
+    /// Position of the output within the terminal width, less the horizontal padding
+    #[arg(long, value_enum, default_value_t = layout::Align::Left)]
+    align: layout::Align,
+
+    /// Blank cells around the output as T,R,B,L, V,H or a single number for all sides
+    #[arg(long, default_value = "0", value_parser = layout::parse_padding)]
+    padding: layout::Padding,
+
+    /// Text printed centered below the output, or auto for the file name
+    #[arg(long)]
+    caption: Option<String>,
+
+    /// Append the dimensions of the image and the size of local files to the caption
+    #[arg(long)]
+    show_dimensions: bool,
+
+    /// Box drawn around the output of character modes
+    #[arg(long, value_enum, default_value_t = layout::Border::None)]
+    border: layout::Border,
+
+    /// Title shown in the top edge of the border
+    #[arg(long, requires = "border")]
+    border_title: Option<String>,
+
+    /// Blank columns between images rendered side by side
+    #[arg(long, default_value_t = 2)]
+    gutter: usize,
+
+    /// Seconds each image is shown when a directory or glob pattern is given
+    #[arg(long, default_value_t = 5.0, value_parser = parse_positive)]
+    interval: f64,
+
+    /// Show the images of a directory or glob pattern in random order
+    #[arg(long)]
+    shuffle: bool,
+
+    /// Redraw whenever one of the local input files changes or the terminal is resized
+    #[arg(long)]
+    watch: bool,
+
+    /// Always download remote images instead of reusing them from the cache in $XDG_CACHE_HOME/tcolr
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Seconds for which a cached download is reused. Downloads are cached per URL and --header headers
+    #[arg(long, default_value_t = 86400.0, value_parser = parse_non_negative)]
+    cache_ttl: f64,
+
+    /// Seconds after which a download is abandoned, 0 waits forever
+    #[arg(long, default_value_t = 30.0, value_parser = parse_non_negative)]
+    timeout: f64,
+
+    /// Number of times a download is retried after timeouts, connection failures and server errors
+    #[arg(long, default_value_t = 2)]
+    retries: u32,
+
+    /// Proxy URL for all downloads, by default the proxy environment variables are honoured
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// Do not show download progress and warnings on stderr
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Print the time spent parsing arguments, fetching, decoding, aggregating, rendering and printing to stderr
+    #[arg(long)]
+    timing: bool,
+
+    /// Refuse to decode images with more pixels, checked before decoding. By default the decoder allocates at most 512 MiB
+    #[arg(long)]
+    max_pixels: Option<u64>,
+
+    /// Refuse inputs larger than this many bytes, checked before reading local files and while downloading
+    #[arg(long)]
+    max_bytes: Option<u64>,
+
+    /// Render the image a web page shows, announced for link previews or its largest, when a URL returns HTML
+    #[arg(long)]
+    extract_page_image: bool,
+
+    /// Number of images downloaded at the same time
+    #[arg(short, long, default_value_t = 4)]
+    jobs: usize,
+
+    /// Extra request header as Name:Value, may be repeated
+    #[arg(long = "header", value_parser = http::parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// Pick chunk sizes so that the image fits the terminal, or the box given by --cols and --rows
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "contain")]
+    fit: Option<Fit>,
+
+    /// Draw every pixel as one chunk, shrinking images larger than the terminal or the box given by --cols and --rows with nearest neighbour sampling
+    #[arg(long, conflicts_with = "fit")]
+    pixel_perfect: bool,
+
+    /// Width in terminal cells of the box the image is fitted into. Without --rows the height follows from the aspect ratio
+    #[arg(long, visible_alias = "width")]
+    cols: Option<usize>,
+
+    /// Height in terminal cells of the box the image is fitted into. Without --cols the width follows from the aspect ratio
+    #[arg(long, visible_alias = "height")]
+    rows: Option<usize>,
+
+    /// Size of the box the image is fitted into as a percentage like 50% of the terminal, or of --cols and --rows
+    #[arg(long, value_parser = parse_percent)]
+    scale: Option<f64>,
+
+    /// Width to height ratio of a terminal cell, used when sizing to fit
+    #[arg(long, default_value_t = 0.5, value_parser = parse_positive)]
+    cell_aspect: f64,
+
+    /// Fit the image into a box this many columns wide, for file manager preview panes
+    #[arg(long)]
+    preview_width: Option<usize>,
+
+    /// Fit the image into a box this many rows high, for file manager preview panes
+    #[arg(long)]
+    preview_height: Option<usize>,
+
+    /// Download remote images for previews instead of only showing cached ones
+    #[arg(long)]
+    preview_fetch: bool,
+
+    /// Do not end the output with a newline
+    #[arg(long)]
+    no_trailing_newline: bool,
+
+    /// Do not print errors, only report them through the exit code
+    #[arg(long)]
+    silent_errors: bool,
+
+    /// Print output taller than the terminal in full instead of paging through it
+    #[arg(long)]
+    no_pager: bool,
+
+    /// Draw a coarse preview of large images first and refine it in place once the full render is ready
+    #[arg(long)]
+    progressive: bool,
+
+    /// Render the same output on every machine, for snapshot tests: the terminal is taken to be 80x24
+    /// with true colour, colours are always emitted, random numbers start from seed 0, images are fetched one at a time and nothing is paged
+    #[arg(long)]
+    deterministic: bool,
+
+    // Set while rendering the coarse first pass of --progressive.
+    #[arg(skip)]
+    preview: bool,
+
+}
+
+impl Args {
+    // Without any image arguments, the image is read from piped standard input.
+    fn inputs(&self) -> Result<Vec<String>, Error> {
+        let mut inputs: Vec<String> = self.image_url.iter().chain(&self.images).cloned().collect();
+        if self.clipboard {
+            inputs.push(String::from(input::CLIPBOARD));
+        }
+        if inputs.is_empty() {
+            if std::io::stdin().is_terminal() {
+                return Err(Error::Usage(String::from("No image given, pass a path or URL or pipe an image to standard input")));
+            }
+            return Ok(vec![String::from(input::STDIN)]);
+        }
+        return Ok(inputs);
+    }
+
+    // Previews fit the box given by their size arguments, with the terminal
+    // width and an unlimited height standing in for a missing one.
+    fn preview_box(&self) -> Option<(usize, usize)> {
+        return match (self.preview_width, self.preview_height) {
+            (None, None) => None,
+            (w, h) => Some((w.unwrap_or_else(|| self.terminal_size().0), h.unwrap_or(usize::MAX)))
+        }
+    }
+
+    fn is_preview(&self) -> bool {
+        return self.preview_box().is_some();
+    }
+
+    // Box the image is fitted into, scaled by --scale. An explicit size on
+    // one axis leaves the other one unlimited. Otherwise the box of a preview
+    // or the terminal is used, leaving the last row of the terminal for the
+    // prompt.
+    fn fit_box(&self) -> (usize, usize) {
+        let (columns, rows) = match (self.cols, self.rows) {
+            (None, None) => self.preview_box().unwrap_or_else(|| {
+                let (columns, rows) = self.terminal_size();
+                (columns, rows.saturating_sub(1))
+            }),
+            (columns, rows) => (columns.unwrap_or(usize::MAX), rows.unwrap_or(usize::MAX))
+        };
+        let scale = |n: usize| match n {
+            usize::MAX => n,
+            n => ((n as f64 * self.scale.unwrap_or(1.0)).round() as usize).max(1)
+        };
+        return (scale(columns), scale(rows));
+    }
+
+    // Previews and explicit box sizes are fitted to their box. Preview panes
+    // read the output through a pipe but show its colours, and have no room
+    // for a progress bar.
+    fn with_preview_defaults(mut self) -> Args {
+        if self.cols.is_some() || self.rows.is_some() || self.scale.is_some() {
+            self.fit = self.fit.or(Some(Fit::Contain));
+        }
+        if self.is_preview() {
+            self.fit = self.fit.or(Some(Fit::Contain));
+            self.quiet = true;
+            if self.color == ColorChoice::Auto {
+                self.color = ColorChoice::Always;
+            }
+        }
+        if self.deterministic {
+            self.colors = self.colors.or(Some(ColorDepth::TrueColor));
+            if self.color == ColorChoice::Auto {
+                self.color = ColorChoice::Always;
+            }
+            self.jobs = 1;
+            self.quiet = true;
+            self.no_pager = true;
+            self.progressive = false;
+            self.seed = self.seed.or(Some(0));
+        }
+        if self.is_graphics() && !self.deterministic && tmux::inside() && !tmux::allows_passthrough() {
+            if !self.quiet {
+                eprintln!("tcolr: tmux does not pass images on to the terminal, drawing half blocks instead. Enable it with `set -g allow-passthrough on`");
+            }
+            self.mode = Mode::HalfBlock;
+        }
+        random::seed(self.seed);
+        return self;
+    }
+
+    // Whether the mode draws images with the sixel or iTerm2 escapes.
+    fn is_graphics(&self) -> bool {
+        return matches!(self.mode, Mode::Sixel | Mode::Iterm2);
+    }
+
+    // Whether the escapes of the graphics modes need wrapping for tmux.
+    fn passthrough(&self) -> bool {
+        return !self.deterministic && tmux::inside();
+    }
+
+    // Size of the terminal, or the size of a default one for --deterministic.
+    fn terminal_size(&self) -> (usize, usize) {
+        if self.deterministic {
+            return DETERMINISTIC_SIZE;
+        }
+        return terminal_size();
+    }
+
+    fn terminal_width(&self) -> usize {
+        return self.terminal_size().0;
+    }
+
+    fn average_space(&self) -> AverageSpace {
+        return if self.linear { AverageSpace::Linear } else { self.average_space };
+    }
+
+    fn first_input(&self) -> Result<String, Error> {
+        return Ok(self.inputs()?.swap_remove(0));
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn main() {
+    let start = Instant::now();
+    let mut args = timing::measure(Stage::Parse, || Args::parse().with_preview_defaults());
+    if !enable_escapes() && args.color == ColorChoice::Auto {
+        args.color = ColorChoice::Never;
+    }
+
+    let result = run(&args);
+    if args.timing {
+        for line in timing::report(start) {
+            eprintln!("{}", line);
+        }
+    }
+    if let Err(error) = result {
+        if !args.silent_errors {
+            eprintln!("tcolr: {}", error);
+        }
+        std::process::exit(error.exit_code());
+    }
+}
+
+// Turns on the VT processing of the Windows console, without which it prints
+// escapes as text, and tells whether escapes work. Terminals like mintty are
+// not consoles but set TERM.
+#[cfg(windows)]
+fn enable_escapes() -> bool {
+    return ansi_term::enable_ansi_support().is_ok() || std::env::var("TERM").is_ok_and(|t| t != "dumb");
+}
+
+#[cfg(not(windows))]
+fn enable_escapes() -> bool {
+    return true;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run(args: &Args) -> Result<(), Error> {
+    if let Some(command) = &args.command {
+        return run_command(command, args);
+    }
+
+    if args.output_file.is_some() && (args.animate || args.watch) {
+        return Err(Error::Usage(String::from("--output-file cannot be used with --animate or --watch")));
+    }
+    if args.animate || args.frame.is_some() || args.frames.is_some() {
+        return animate(args);
+    }
+
+    let inputs = match input::expand(&args.inputs()?)? {
+        (_, true) if args.output_file.is_some() => return Err(Error::Usage(String::from("--output-file cannot be used with a slideshow"))),
+        (inputs, true) => return slide_show(&inputs, args),
+        (inputs, false) => inputs
+    };
+    if args.watch {
+        return watch_inputs(&inputs, args);
+    }
+    if args.progressive && inputs.len() == 1 && is_refinable(args) {
+        return render_progressively(&inputs[0], args);
+    }
+    let lines = render_inputs(&inputs, args)?;
+    if is_pageable(&lines, args) {
+        return pager::run(&lines).map_err(|e| Error::Render(format!("Unable to run the pager: {}", e)));
+    }
+    return timing::measure(Stage::Print, || write_lines(&lines, args));
+}
+
+// Output taller than the terminal is paged rather than scrolling its top off
+// screen, unless it is redirected.
+fn is_pageable(lines: &[String], args: &Args) -> bool {
+    return !args.no_pager
+        && !args.is_preview()
+        && args.output == Output::Ansi
+        && args.output_file.is_none()
+        && args.mode != Mode::Sixel
+        && args.mode != Mode::Iterm2
+        && std::io::stdin().is_terminal()
+        && std::io::stdout().is_terminal()
+        && lines.len() >= args.terminal_size().1;
+}
+
+// The preview is redrawn by moving the cursor back up, which needs a terminal
+// and the same number of text lines in both passes.
+fn is_refinable(args: &Args) -> bool {
+    return args.output == Output::Ansi
+        && args.output_file.is_none()
+        && args.mode != Mode::Sixel
+        && args.mode != Mode::Iterm2
+        && std::io::stdout().is_terminal();
+}
+
+// Shows a coarse preview while the full render is computed, then overwrites it.
+// Previews that would scroll off the top of the terminal are skipped.
+fn render_progressively(uri: &String, args: &Args) -> Result<(), Error> {
+    let image = get_image(uri, &decode_options(args))?;
+    let preview = finish(render_captioned(uri, &image, &Args { preview: true, save_thumbnail: None, ..args.clone() })?, args);
+    let shown = preview.len() < args.terminal_size().1;
+    if shown {
+        emit(BufWriter::new(std::io::stdout().lock()), "", &preview, TERMINAL_LINE_BREAK, true)
+            .map_err(|e| Error::Render(format!("Unable to write output: {}", e)))?;
+    }
+    let lines = finish(render_captioned(uri, &image, args)?, args);
+    let home = if shown { format!("\x1b[{}A\r", preview.len()) } else { String::new() };
+    return timing::measure(Stage::Print, || emit(BufWriter::new(std::io::stdout().lock()), &home, &lines, TERMINAL_LINE_BREAK, true))
+        .map_err(|e| Error::Render(format!("Unable to write output: {}", e)));
+}
+
+// Writes the final output to `--output-file`, or standard output. Binary
+// output is written while rendering and leaves no lines.
+fn write_lines(lines: &[String], args: &Args) -> Result<(), Error> {
+    if args.output.is_binary() {
+        return Ok(());
+    }
+    let result = match &args.output_file {
+        Some(path) => File::create(path).and_then(|f| emit(BufWriter::new(f), "", lines, b"\n", !args.no_trailing_newline)),
+        None => {
+            let stdout = std::io::stdout().lock();
+            let line_break = if stdout.is_terminal() { TERMINAL_LINE_BREAK } else { b"\n" };
+            emit(BufWriter::new(stdout), "", lines, line_break, !args.no_trailing_newline)
+        }
+    };
+    return result.map_err(|e| Error::Render(format!("Unable to write output: {}", e)));
+}
+
+// Line break written between lines on a terminal. The Windows console only
+// returns the cursor on `\n` while DISABLE_NEWLINE_AUTO_RETURN is off, which
+// programs run before may have left on.
+#[cfg(windows)]
+const TERMINAL_LINE_BREAK: &[u8] = b"\r\n";
+#[cfg(not(windows))]
+const TERMINAL_LINE_BREAK: &[u8] = b"\n";
+
+// Writes `prefix` followed by `lines` through a single buffer, rather than
+// one write to the line buffered stdout per line.
+fn emit<W: Write>(mut out: W, prefix: &str, lines: &[String], line_break: &[u8], trailing_newline: bool) -> std::io::Result<()> {
+    out.write_all(prefix.as_bytes())?;
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            out.write_all(line_break)?;
+        }
+        out.write_all(line.as_bytes())?;
+    }
+    if trailing_newline {
+        out.write_all(line_break)?;
+    }
+    return out.flush();
+}
+
+fn write_output(data: &[u8], args: &Args) -> Result<(), Error> {
+    let result = match &args.output_file {
+        Some(path) => std::fs::write(path, data),
+        None => std::io::stdout().write_all(data)
+    };
+    return result.map_err(|e| Error::Render(format!("Unable to write output: {}", e)));
+}
+
+fn depth(args: &Args) -> ColorDepth {
+    let choice = if args.force_color { ColorChoice::Always } else { args.color };
+    // HTML carries the colours as markup, whatever the terminal supports.
+    if args.output == Output::Html && choice != ColorChoice::Never {
+        return args.colors.unwrap_or(ColorDepth::TrueColor);
+    }
+    let terminal = args.output_file.is_none() && std::io::stdout().is_terminal();
+    return palette::resolve(choice, args.colors, terminal);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run_command(command: &Command, args: &Args) -> Result<(), Error> {
+    return match command {
+        Command::Palette { image, count, format } => print_palette(image, *count, *format, args),
+        Command::Avg { image } => print_average(image, args),
+        Command::Histogram { image, bins, height } => print_histogram(image, *bins, *height, args),
+        Command::View { image } => view(image, args),
+        Command::Diff { first, second, highlight, threshold } => diff(first, second, *highlight, *threshold, args),
+        Command::Compare { first, second, metric, threshold } => compare(first, second, *metric, *threshold, args),
+        Command::Bench { image, sizes, chunks, iterations } => {
+            let options = decode_options(args);
+            let source = image.as_ref().map(|uri| get_image(uri, &options)).transpose()?;
+            write_lines(&bench::run(source.as_ref(), sizes, chunks, *iterations, &options, args)?, args)
+        },
+        Command::Completions { shell } => {
+            let mut script = Vec::new();
+            clap_complete::generate(*shell, &mut Args::command(), "tcolr", &mut script);
+            write_output(&script, args)
+        }
+    }
+}
+
+// Renders the visible region of the image to fit the terminal, above the
+// status line of the viewer.
+#[cfg(not(target_arch = "wasm32"))]
+fn view(uri: &String, args: &Args) -> Result<(), Error> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(Error::Usage(String::from("The viewer requires a terminal")));
+    }
+    if args.mode == Mode::Sixel || args.mode == Mode::Iterm2 || args.output != Output::Ansi {
+        return Err(Error::Usage(String::from("The viewer requires a character mode and ANSI output")));
+    }
+    let image = get_image(uri, &decode_options(args))?;
+    let result = viewer::run(|view| {
+        let (x, y, width, height) = view.region(image.width(), image.height());
+        let mut filter_fx = args.filter_fx.clone();
+        if view.grayscale {
+            filter_fx.push(effects::Effect::Grayscale);
+        }
+        let region = image.crop_imm(x, y, width, height);
+        let visible = Args { fit: Some(Fit::Contain), cols: None, rows: None, scale: None, crop: None, save_thumbnail: None, filter_fx, ..args.clone() };
+        render_image(&region, &visible).unwrap_or_else(|error| vec![error.to_string()])
+    });
+    return result.map_err(|e| Error::Render(format!("Unable to run the viewer: {}", e)));
+}
+
+// Colour of the cells that differ in a highlighted diff.
+const DIFF_HIGHLIGHT: Colour = Colour::RGB(255, 0, 0);
+
+// Renders two images through the same grid, either side by side or as the
+// first image dimmed with the cells that differ from the second highlighted.
+fn diff(first: &str, second: &str, highlight: bool, threshold: f64, args: &Args) -> Result<(), Error> {
+    if args.mode == Mode::Sixel || args.mode == Mode::Iterm2 || args.output.is_grid() {
+        return Err(Error::Usage(String::from("diff requires a character mode and ANSI or HTML output")));
+    }
+    let (a, b) = load_pair(first, second, args)?;
+
+    let columns = if highlight { 1 } else { 2 };
+    let cell_width = (args.terminal_width().saturating_sub(args.gutter * (columns - 1)) / columns).max(1);
+    let fitted = fit_args(&a, cell_width, usize::MAX, args);
+    let (grid_a, grid_b) = (colour_grid(&a, &fitted), colour_grid(&b, &fitted));
+    let depth = depth(args);
+    let lines = if highlight {
+        let mut changed = 0;
+        let cells = grid_a.cells.iter().zip(&grid_b.cells)
+            .map(|(ca, cb)| {
+                let distance = (palette::distance(palette::to_rgb(*ca), palette::to_rgb(*cb)) as f64).sqrt();
+                if distance > threshold {
+                    changed += 1;
+                    return DIFF_HIGHLIGHT;
+                }
+                let dimmed = (render::luminance(ca) / 2.0) as u8;
+                return Colour::RGB(dimmed, dimmed, dimmed);
+            })
+            .collect();
+        let grid = Grid { n_x: grid_a.n_x, n_y: grid_a.n_y, cells };
+        let mut lines = render_grid(&grid, None, depth, &fitted)?;
+        lines.push(format!("{} of {} cells differ by more than {}", changed, grid.cells.len(), threshold));
+        lines
+    } else {
+        let blocks = [render_grid(&grid_a, None, depth, &fitted)?, render_grid(&grid_b, None, depth, &fitted)?];
+        montage::compose(&blocks, columns, cell_width, args.gutter)
+    };
+    return write_lines(&finish(lines, args), args);
+}
+
+// Fetches two images concurrently and crops them. The second one is resized
+// to the size of the first, so that the chunks of both cover the same parts.
+fn load_pair(first: &str, second: &str, args: &Args) -> Result<(DynamicImage, DynamicImage), Error> {
+    let inputs = [first.to_owned(), second.to_owned()];
+    let options = concurrent_decode_options(&inputs, args);
+    let mut images = input::fetch_all(&inputs, args.jobs, |uri| get_image(uri, &options))
+        .into_iter()
+        .map(|image| match args.crop {
+            Some(region) => image.and_then(|i| region.apply(&i).map_err(Error::Usage)),
+            None => image
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let b = images.pop().expect("Two images were fetched");
+    let a = images.pop().expect("Two images were fetched");
+    let b = match b.dimensions() == a.dimensions() {
+        true => b,
+        false => b.resize_exact(a.width(), a.height(), FilterType::Triangle)
+    };
+    return Ok((a, b));
+}
+
+// Prints a similarity metric of the chunk colours of two images, failing with
+// `Error::Mismatch` if it is worse than the threshold.
+fn compare(first: &str, second: &str, metric: Metric, threshold: Option<f64>, args: &Args) -> Result<(), Error> {
+    let (a, b) = load_pair(first, second, args)?;
+    let (grid_a, grid_b) = (colour_grid(&a, args), colour_grid(&b, args));
+    let rgb = |grid: &Grid<Colour>| grid.cells.iter().map(|c| palette::to_rgb(*c)).collect::<Vec<_>>();
+    let value = metrics::compute(metric, &rgb(&grid_a), &rgb(&grid_b), grid_a.n_x);
+    let name = metric.to_possible_value().map(|v| v.get_name().to_owned()).unwrap_or_default();
+    write_lines(&[format!("{} {:.4}", name, value)], args)?;
+    return match threshold {
+        Some(t) if metric.higher_is_better() && value < t => Err(Error::Mismatch(format!("{} {:.4} is below the threshold of {}", name, value, t))),
+        Some(t) if !metric.higher_is_better() && value > t => Err(Error::Mismatch(format!("{} {:.4} is above the threshold of {}", name, value, t))),
+        _ => Ok(())
+    }
+}
+
+// Chunk colours of `image` as they are rendered with the arguments, with
+// transparent pixels composited over the background.
+fn colour_grid(image: &DynamicImage, args: &Args) -> Grid<Colour> {
+    let buf = match image {
+        DynamicImage::ImageRgba8(buf) => flatten(buf, palette::to_rgb(args.background.unwrap_or(Colour::RGB(0, 0, 0)))),
+        i if tonemap::is_high_depth(i) => return colour_grid(&tonemap::apply(i, args.tonemap), args),
+        i => i.to_rgb8()
+    };
+    let depth = depth(args);
+    return match (args.aggregate, args.average_space()) {
+        (Aggregate::Mean, AverageSpace::Srgb) => aggregate_grid(&buf, RGBSum::zero(), depth, args),
+        (Aggregate::Mean, AverageSpace::Linear) => aggregate_grid(&buf, LinearRGBSum::zero(), depth, args),
+        (Aggregate::Mean, AverageSpace::Oklab) => aggregate_grid(&buf, OklabSum::zero(), depth, args),
+        (a, _) => aggregate_grid(&buf, Collected::new(a), depth, args)
+    }
+}
+
+fn print_histogram(uri: &String, bins: usize, height: usize, args: &Args) -> Result<(), Error> {
+    let image = get_image(uri, &decode_options(args))?;
+    let depth = depth(args);
+    let channels = [
+        ("red", Colour::RGB(255, 0, 0)),
+        ("green", Colour::RGB(0, 255, 0)),
+        ("blue", Colour::RGB(0, 0, 255)),
+        ("luminance", Colour::RGB(255, 255, 255)),
+    ];
+    let mut lines = Vec::new();
+    for (counts, (name, colour)) in histogram::compute(&image).iter().zip(channels) {
+        lines.push(name.to_owned());
+        lines.extend(histogram::render(counts, bins, height, colour, depth));
+    }
+    return write_lines(&lines, args);
+}
+
+fn print_palette(uri: &String, count: usize, format: export::PaletteFormat, args: &Args) -> Result<(), Error> {
+    let image = get_image(uri, &decode_options(args))?;
+    let swatches = dominant::extract(&image, count);
+    if format != export::PaletteFormat::Swatches {
+        return write_lines(&export::palette(&swatches, format), args);
+    }
+    let depth = depth(args);
+    let lines: Vec<String> = swatches.iter()
+        .map(|swatch| {
+            let (r, g, b) = swatch.rgb;
+            let block = palette::background(Colour::RGB(r, g, b), depth).paint("      ");
+            format!("{} #{:02x}{:02x}{:02x} {:5.1}%", block, r, g, b, swatch.share * 100.0)
+        })
+        .collect();
+    return write_lines(&lines, args);
+}
+
+// Only the values are printed without colours, for scripts. Fully transparent
+// pixels do not count towards the average.
+fn print_average(uri: &String, args: &Args) -> Result<(), Error> {
+    let image = get_image(uri, &decode_options(args))?;
+    let mut sum = LinearRGBSum::zero();
+    let mut n = 0;
+    for p in image.to_rgba8().pixels().filter(|p| p[3] > 0) {
+        sum.aggregate(p);
+        n += 1;
+    }
+    sum.div_inplace(n.max(1));
+    let colour = sum.to_colour();
+    let (r, g, b) = render::rgb_components(&colour);
+    let value = format!("#{:02x}{:02x}{:02x} rgb({}, {}, {})", r, g, b, r, g, b);
+    let mut lines = match depth(args) {
+        ColorDepth::NoColor => Vec::new(),
+        depth => vec![palette::background(colour, depth).paint(" ".repeat(16)).to_string(); 4]
+    };
+    lines.push(value);
+    return write_lines(&lines, args);
+}
+
+fn render_inputs(inputs: &[String], args: &Args) -> Result<Vec<String>, Error> {
+    let options = concurrent_decode_options(inputs, args);
+    if inputs.len() > 1 && args.save_thumbnail.is_some() {
+        return Err(Error::Usage(String::from("--save-thumbnail requires a single image")));
+    }
+    if inputs.len() > 1 && args.output.is_binary() {
+        return Err(Error::Usage(String::from("Binary output requires a single image")));
+    }
+    let images = input::fetch_all(inputs, args.jobs, |uri| get_image(uri, &options))
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    let lines = if images.len() == 1 {
+        render_captioned(&inputs[0], &images[0], args)?
+    } else {
+        render_montage(inputs, &images, args)?
+    };
+    return Ok(finish(lines, args));
+}
+
+// Frames text rendered with colour escapes, places it according to the
+// alignment and padding, and converts it to the requested output format.
+// Lines of the graphics protocols have no known width and are only padded.
+fn finish(lines: Vec<String>, args: &Args) -> Vec<String> {
+    if args.output.is_grid() {
+        return lines;
+    }
+    let (align, lines) = match args.mode {
+        Mode::Sixel | Mode::Iterm2 => (layout::Align::Left, lines),
+        _ => (args.align, layout::frame(lines, args.border, args.border_title.as_deref()))
+    };
+    let width = args.preview_box().map_or_else(|| args.terminal_width(), |(columns, _)| columns);
+    let forward = args.output == Output::Ansi && depth(args) != ColorDepth::NoColor;
+    let lines = layout::place(lines, width, align, args.padding, forward);
+    return match args.output {
+        Output::Html => export::html(&lines),
+        _ => lines
+    }
+}
+
+// Progress bars of simultaneous downloads would overwrite each other.
+fn concurrent_decode_options(inputs: &[String], args: &Args) -> DecodeOptions {
+    let mut options = decode_options(args);
+    if inputs.iter().filter(|u| input::is_remote(u)).count() > 1 {
+        options.http.progress = false;
+    }
+    return options;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn watch_inputs(inputs: &[String], args: &Args) -> Result<(), Error> {
+    let paths: Vec<std::path::PathBuf> = inputs.iter()
+        .filter_map(|uri| match uri {
+            u if u.starts_with("file://") => u.strip_prefix("file://"),
+            u if !u.contains("://") && !u.starts_with("data:") && u != input::STDIN && u != input::CLIPBOARD => Some(u.as_str()),
+            _ => None
+        })
+        .map(std::path::PathBuf::from)
+        .collect();
+    if paths.is_empty() {
+        return Err(Error::Usage(String::from("--watch requires at least one local file")));
+    }
+    let mut redraw = redraw::Redraw::new();
+    let mut size = terminal_size();
+    let result = watch::run(&paths, || {
+        // Files may be caught half written, the next change redraws them.
+        let lines = render_inputs(inputs, args).unwrap_or_else(|error| vec![error.to_string()]);
+        // The terminal reflows its content when resized.
+        if terminal_size() != size {
+            size = terminal_size();
+            redraw.reset();
+        }
+        let mut out = std::io::stdout().lock();
+        let update = if args.is_graphics() { redraw.full(&lines) } else { redraw.update(&lines) };
+        let _ = out.write_all(update.as_bytes()).and_then(|_| out.flush());
+    });
+    return result.map_err(|e| Error::Render(format!("Unable to watch {:?}: {}", paths, e)));
+}
+
+fn decode_options(args: &Args) -> DecodeOptions {
+    return DecodeOptions {
+        cache_ttl: if args.no_cache { None } else { Some(Duration::from_secs_f64(args.cache_ttl)) },
+        http: HttpOptions {
+            timeout: if args.timeout > 0.0 { Some(Duration::from_secs_f64(args.timeout)) } else { None },
+            retries: args.retries,
+            proxy: args.proxy.clone(),
+            headers: args.headers.clone(),
+            progress: !args.quiet,
+            max_bytes: args.max_bytes,
+        },
+        chunk_size: (args.x_chunks as u32, args.y_chunks as u32),
+        timestamp: args.timestamp,
+        page: args.page as usize - 1,
+        icon_size: args.icon_size,
+        max_pixels: args.max_pixels,
+        max_bytes: args.max_bytes,
+        cached_only: args.is_preview() && !args.preview_fetch,
+        extract_page_image: args.extract_page_image,
+    };
+}
+
+fn parse_positive(s: &str) -> Result<f64, String> {
+    return match s.parse::<f64>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(v),
+        _ => Err(format!("Expected a positive number: {}", s))
+    }
+}
+
+// Parses a chunk size, which divides the image and so cannot be 0.
+fn parse_chunk_size(s: &str) -> Result<usize, String> {
+    return match s.parse::<usize>() {
+        Ok(v) if v > 0 => Ok(v),
+        _ => Err(format!("Expected a positive whole number of pixels: {}", s))
+    }
+}
+
+fn parse_non_negative(s: &str) -> Result<f64, String> {
+    return match s.parse::<f64>() {
+        Ok(v) if v.is_finite() && v >= 0.0 => Ok(v),
+        _ => Err(format!("Expected a non-negative number: {}", s))
+    }
+}
+
+fn parse_level(s: &str) -> Result<f64, String> {
+    return match s.parse::<f64>() {
+        Ok(v) if (0.0 ..= 1.0).contains(&v) => Ok(v),
+        _ => Err(format!("Expected a number from 0 to 1: {}", s))
+    }
+}
+
+// Parses a positive percentage like `50%` into a fraction.
+fn parse_percent(s: &str) -> Result<f64, String> {
+    return match s.trim().strip_suffix('%').unwrap_or(s.trim()).parse::<f64>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(v / 100.0),
+        _ => Err(format!("Expected a positive percentage: {}", s))
+    }
+}
+
+// Parses a playback speed like `2x` or `0.5`.
+fn parse_speed(s: &str) -> Result<f64, String> {
+    return parse_positive(s.trim().strip_suffix('x').unwrap_or(s.trim()))
+        .map_err(|_| format!("Expected a speed like 2x: {}", s));
+}
+
+// Parses a width in pixels, or `best` for the largest one.
+fn parse_icon_size(s: &str) -> Result<u32, String> {
+    return match s {
+        "best" => Ok(u32::MAX),
+        s => s.parse().map_err(|_| format!("Expected a width in pixels or best: {}", s))
+    }
+}
+
+fn parse_charset(s: &str) -> Result<String, String> {
+    if s.is_empty() {
+        return Err(String::from("The character ramp must not be empty"));
+    }
+    return Ok(s.to_owned());
+}
+
+// Parses `[[HH:]MM:]SS[.fff]` into a duration.
+fn parse_timestamp(s: &str) -> Result<Duration, String> {
+    let mut seconds = 0.0;
+    for part in s.split(':') {
+        let value: f64 = part.parse().map_err(|_| format!("Invalid timestamp: {}", s))?;
+        seconds = seconds * 60.0 + value;
+    }
+    if s.split(':').count() > 3 || !seconds.is_finite() || seconds < 0.0 {
+        return Err(format!("Invalid timestamp: {}", s));
+    }
+    return Ok(Duration::from_secs_f64(seconds));
+}
+
+// Renders the image with its caption below, if one is requested. Exported
+// grids have no room for one.
+fn render_captioned(uri: &str, image: &DynamicImage, args: &Args) -> Result<Vec<String>, Error> {
+    let lines = render_image(image, args)?;
+    let mut parts = Vec::new();
+    match args.caption.as_deref() {
+        Some(caption::AUTO) => parts.push(caption::file_name(uri)),
+        Some(text) => parts.push(text.to_owned()),
+        None => {}
+    }
+    if args.show_dimensions {
+        parts.push(format!("{}x{}", image.width(), image.height()));
+        let path = uri.strip_prefix("file://").unwrap_or(uri);
+        match std::fs::metadata(path) {
+            Ok(metadata) if !input::is_remote(uri) => parts.push(caption::format_size(metadata.len())),
+            _ => {}
+        }
+    }
+    if parts.is_empty() || args.output.is_grid() {
+        return Ok(lines);
+    }
+    let centered = args.mode != Mode::Sixel && args.mode != Mode::Iterm2;
+    return Ok(caption::below(lines, &parts.join(" · "), centered));
+}
+
+fn print_image(image: &DynamicImage, args: &Args) -> Result<(), Error> {
+    return write_lines(&finish(render_image(image, args)?, args), args);
+}
+
+fn render_image(image: &DynamicImage, args: &Args) -> Result<Vec<String>, Error> {
+    if let Some(region) = args.crop {
+        let uncropped = Args { crop: None, ..args.clone() };
+        let cropped = region.apply(image).map_err(Error::Usage)?;
+        return render_image(&cropped, &uncropped);
+    }
+    if args.output == Output::Html && (args.mode == Mode::Sixel || args.mode == Mode::Iterm2) {
+        return Err(Error::Usage(String::from("HTML output requires a character mode")));
+    }
+    if args.pixel_perfect {
+        return render_pixels(image, args);
+    }
+    if let Some(fit) = args.fit {
+        let (columns, rows) = args.fit_box();
+        // Boxes unlimited along one axis cannot be filled, only fitted.
+        let bounded = columns != usize::MAX && rows != usize::MAX;
+        let (image, fitted) = match fit {
+            Fit::Cover if bounded => cover(image, columns, rows, args),
+            Fit::Stretch if bounded => stretch(image, columns, rows, args),
+            _ => return render_image(image, &fit_args(image, columns, rows, args))
+        };
+        return render_image(&image, &fitted);
+    }
+    if args.preview {
+        return render_preview(image, args);
+    }
+    if let (Some(k), DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_)) = (&args.kernel, image) {
+        return render_image(&kernel::apply(image, k), &Args { kernel: None, ..args.clone() });
+    }
+    return match image {
+        i if args.mode == Mode::Iterm2 && !args.output.is_grid() => render::iterm2(i, (i.width() as usize).div_ceil(args.x_chunks), args.passthrough())
+            .map_err(|e| Error::Render(format!("Unable to encode image for iTerm2: {}", e))),
+        i @ (DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_)) if args.auto_contrast || args.equalize => {
+            let levelled = Args { auto_contrast: false, equalize: false, ..args.clone() };
+            render_image(&levels::apply(i, args.auto_contrast, args.equalize), &levelled)
+        },
+        DynamicImage::ImageRgb8(buf) if sharpens_image(args) => render_buffer(&sharpen::image(buf, args.sharpen, args.x_chunks, args.y_chunks), None, args),
+        DynamicImage::ImageRgb8(buf) => render_buffer(buf, None, args),
+        DynamicImage::ImageRgba8(buf) => {
+            let mask = match (args.mode, args.background) {
+                (Mode::HalfBlock, None) => Some(sample_chunks(buf, args.x_chunks, args.y_chunks, Coverage::zero())),
+                _ => None
+            };
+            let matte = palette::to_rgb(args.background.unwrap_or(Colour::RGB(0, 0, 0)));
+            let flat = flatten(buf, matte);
+            match sharpens_image(args) {
+                true => render_buffer(&sharpen::image(&flat, args.sharpen, args.x_chunks, args.y_chunks), mask, args),
+                false => render_buffer(&flat, mask, args)
+            }
+        },
+        i if tonemap::is_high_depth(i) => render_image(&tonemap::apply(i, args.tonemap), args),
+        // Grayscale and paletted images are converted to 8 bit RGB, keeping
+        // the alpha channel of those that have one.
+        i if i.color().has_alpha() => render_image(&DynamicImage::ImageRgba8(i.to_rgba8()), args),
+        i => render_image(&DynamicImage::ImageRgb8(i.to_rgb8()), args)
+    }
+}
+
+fn sharpens_image(args: &Args) -> bool {
+    return args.sharpen > 0.0 && args.sharpen_stage == sharpen::Stage::Before;
+}
+
+// Renders one chunk per pixel, of the image itself if it fits the box and
+// otherwise of a copy shrunk to fit with nearest neighbour sampling, which
+// keeps the hard edges of pixel art.
+fn render_pixels(image: &DynamicImage, args: &Args) -> Result<Vec<String>, Error> {
+    let exact = Args { pixel_perfect: false, fit: None, x_chunks: 1, y_chunks: 1, filter: Filter::Box, ..args.clone() };
+    let (columns, rows) = args.fit_box();
+    let (per_column, per_row) = args.mode.chunks_per_cell();
+    let width = columns.saturating_mul(per_column).min(u32::MAX as usize) as u32;
+    let height = rows.saturating_mul(per_row).min(u32::MAX as usize) as u32;
+    if image.width() <= width && image.height() <= height {
+        return render_image(image, &exact);
+    }
+    return render_image(&image.resize(width, height, FilterType::Nearest), &exact);
+}
+
+// Cells of the preview are grouped into blocks of this many cells per side.
+const PREVIEW_BLOCK: usize = 4;
+
+// Every block of cells in the preview shows the single pixel at the centre of
+// the block, so the preview has the same layout as the full render at a
+// fraction of the cost of sampling every pixel.
+fn render_preview(image: &DynamicImage, args: &Args) -> Result<Vec<String>, Error> {
+    let n_x = (image.width() as usize).div_ceil(args.x_chunks);
+    let n_y = (image.height() as usize).div_ceil(args.y_chunks);
+    let (block_x, block_y) = (PREVIEW_BLOCK * args.x_chunks, PREVIEW_BLOCK * args.y_chunks);
+    let centre = |cell: u32, block: usize, size: u32| {
+        let start = cell as usize / PREVIEW_BLOCK * block;
+        (start + block / 2).min(size as usize - 1) as u32
+    };
+    let coarse = RgbaImage::from_fn(n_x as u32, n_y as u32, |x, y| image.get_pixel(
+        centre(x, block_x, image.width()),
+        centre(y, block_y, image.height())
+    ));
+    let cells = Args { x_chunks: 1, y_chunks: 1, filter: Filter::Box, preview: false, ..args.clone() };
+    return render_image(&DynamicImage::ImageRgba8(coarse), &cells);
+}
+
+// Composites `buf` over an opaque `matte` colour.
+fn flatten(buf: &RgbaImage, matte: (u8, u8, u8)) -> RgbImage {
+    let matte = [matte.0, matte.1, matte.2];
+    return RgbImage::from_fn(buf.width(), buf.height(), |x, y| {
+        let p = buf.get_pixel(x, y);
+        let alpha = p[3] as u32;
+        Rgb([0, 1, 2].map(|c| ((p[c] as u32 * alpha + matte[c] as u32 * (255 - alpha) + 127) / 255) as u8))
+    });
+}
+
+fn render_buffer<P: Pixel<Subpixel: ChannelSum> + 'static, C: Deref<Target = [P::Subpixel]>>(buf: &ImageBuffer<P, C>, mask: Option<Grid<Coverage>>, args: &Args) -> Result<Vec<String>, Error>
+where RGBSum: Aggregator<P>, LinearRGBSum: Aggregator<P>, OklabSum: Aggregator<P>, Collected: Aggregator<P> {
+    return match (args.aggregate, args.average_space()) {
+        (Aggregate::Mean, AverageSpace::Srgb) => parse_image_and_render(buf, RGBSum::zero(), mask, args),
+        (Aggregate::Mean, AverageSpace::Linear) => parse_image_and_render(buf, LinearRGBSum::zero(), mask, args),
+        (Aggregate::Mean, AverageSpace::Oklab) => parse_image_and_render(buf, OklabSum::zero(), mask, args),
+        (a, _) => parse_image_and_render(buf, Collected::new(a), mask, args),
+    }
+}
+
+// Columns and rows of the terminal assumed by --deterministic.
+const DETERMINISTIC_SIZE: (usize, usize) = (80, 24);
+
+fn env_size(name: &str, default: usize) -> usize {
+    return std::env::var(name).ok().and_then(|c| c.parse().ok()).unwrap_or(default);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn terminal_size() -> (usize, usize) {
+    return match crossterm::terminal::size() {
+        Ok((columns, rows)) if columns > 0 && rows > 0 => (columns as usize, rows as usize),
+        _ => (env_size("COLUMNS", 80), env_size("LINES", 24))
+    }
+}
+
+// There is no terminal to ask, only the size given by --cols and --rows.
+#[cfg(target_arch = "wasm32")]
+fn terminal_size() -> (usize, usize) {
+    return (env_size("COLUMNS", 80), env_size("LINES", 24));
+}
+
+// Copy of `args` with the smallest chunk sizes that render `image` within
+// `columns` x `rows` terminal cells. Chunks are as much taller than wide as
+// their share of a cell, so that the image keeps its aspect ratio on screen.
+fn fit_args(image: &DynamicImage, columns: usize, rows: usize, args: &Args) -> Args {
+    let (per_column, per_row) = args.mode.chunks_per_cell();
+    let ratio = per_column as f64 / (per_row as f64 * args.cell_aspect);
+    let x_for_width = image.width() as f64 / columns.max(1).saturating_mul(per_column) as f64;
+    let x_for_height = image.height() as f64 / rows.max(1).saturating_mul(per_row) as f64 / ratio;
+    let x_chunks = x_for_width.max(x_for_height).ceil().max(1.0) as usize;
+    return Args { fit: None, x_chunks, y_chunks: (x_chunks as f64 * ratio).ceil().max(1.0) as usize, ..args.clone() };
+}
+
+// Copy of `image` cropped around its centre, and of `args` with the largest
+// chunk sizes whose chunks fill `columns` x `rows` terminal cells, keeping
+// the aspect ratio of the image like `fit_args`. Images smaller than the box
+// are not enlarged.
+fn cover(image: &DynamicImage, columns: usize, rows: usize, args: &Args) -> (DynamicImage, Args) {
+    let (per_column, per_row) = args.mode.chunks_per_cell();
+    let ratio = per_column as f64 / (per_row as f64 * args.cell_aspect);
+    let (n_x, n_y) = (columns.max(1).saturating_mul(per_column), rows.max(1).saturating_mul(per_row));
+    let x_for_width = image.width() as f64 / n_x as f64;
+    let x_for_height = image.height() as f64 / n_y as f64 / ratio;
+    let x_chunks = x_for_width.min(x_for_height).floor().max(1.0) as usize;
+    let y_chunks = (x_chunks as f64 * ratio).floor().max(1.0) as usize;
+    let width = (n_x.saturating_mul(x_chunks) as u64).min(image.width() as u64) as u32;
+    let height = (n_y.saturating_mul(y_chunks) as u64).min(image.height() as u64) as u32;
+    let cropped = image.crop_imm((image.width() - width) / 2, (image.height() - height) / 2, width, height);
+    return (cropped, Args { fit: None, x_chunks, y_chunks, ..args.clone() });
+}
+
+// Copy of `image` resized to a whole number of chunks per axis, and of `args`
+// with the chunk sizes that fill exactly `columns` x `rows` terminal cells.
+fn stretch(image: &DynamicImage, columns: usize, rows: usize, args: &Args) -> (DynamicImage, Args) {
+    let (per_column, per_row) = args.mode.chunks_per_cell();
+    let (n_x, n_y) = (columns.max(1).saturating_mul(per_column), rows.max(1).saturating_mul(per_row));
+    let x_chunks = image.width().div_ceil(n_x as u32).max(1);
+    let y_chunks = image.height().div_ceil(n_y as u32).max(1);
+    let resized = image.resize_exact(n_x as u32 * x_chunks, n_y as u32 * y_chunks, FilterType::Triangle);
+    return (resized, Args { fit: None, x_chunks: x_chunks as usize, y_chunks: y_chunks as usize, ..args.clone() });
+}
+
+// Splits the terminal width evenly between the images of a row and picks
+// chunk sizes per image so that each fits its share, keeping the x:y chunk
+// ratio of the arguments. Graphics protocols and exports cannot be placed
+// side by side and are printed one after another instead.
+fn render_montage(inputs: &[String], images: &[DynamicImage], args: &Args) -> Result<Vec<String>, Error> {
+    if args.mode == Mode::Sixel || args.mode == Mode::Iterm2 || args.output.is_grid() {
+        let blocks = inputs.iter().zip(images)
+            .map(|(uri, image)| render_captioned(uri, image, args))
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(blocks.concat());
+    }
+    let columns = args.grid_columns.unwrap_or(images.len()).clamp(1, images.len());
+    let cell_width = (args.terminal_width().saturating_sub(args.gutter * (columns - 1)) / columns).max(1);
+    let blocks = inputs.iter().zip(images)
+        .map(|(uri, image)| render_captioned(uri, image, &fit_args(image, cell_width, usize::MAX, args)))
+        .collect::<Result<Vec<_>, _>>()?;
+    return Ok(montage::compose(&blocks, columns, cell_width, args.gutter));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn slide_show(inputs: &[String], args: &Args) -> Result<(), Error> {
+    if inputs.is_empty() {
+        return Err(Error::Usage(format!("No images found in {:?}", args.inputs()?)));
+    }
+    // Remote slides are downloaded up front, all at once, and decoded when shown.
+    let mut inputs = inputs.to_vec();
+    if args.shuffle {
+        random::with(|rng| rng.shuffle(&mut inputs));
+    }
+    let inputs = &inputs;
+    let options = concurrent_decode_options(inputs, args);
+    let remote: Vec<String> = inputs.iter().filter(|u| input::is_remote(u)).cloned().collect();
+    let downloads: HashMap<&String, Result<Bytes, Error>> = remote.iter()
+        .zip(input::fetch_all(&remote, args.jobs, |uri| input::get_bytes(uri, &options)))
+        .collect();
+    let interval = Duration::from_secs_f64(args.interval);
+    let result = slideshow::run(inputs.len(), interval, |index| {
+        let uri = &inputs[index];
+        let mut lines = vec![format!("[{}/{}] {}", index + 1, inputs.len(), uri)];
+        let image = match downloads.get(uri) {
+            Some(Ok(bytes)) => input::get_image_from_bytes(bytes.clone(), uri, &options),
+            Some(Err(error)) => Err(Error::Fetch { uri: uri.clone(), source: error.to_string().into() }),
+            None => get_image(uri, &options)
+        };
+        // Slides fit the terminal below the title line as it is sized when
+        // they are drawn, so they are fitted anew after every resize.
+        let (columns, rows) = args.terminal_size();
+        let (cols, rows) = match (args.cols, args.rows) {
+            (None, None) => (Some(columns), Some(rows.saturating_sub(2))),
+            given => given
+        };
+        let slide = Args { fit: args.fit.or(Some(Fit::Contain)), cols, rows, ..args.clone() };
+        match image.and_then(|i| render_image(&i, &slide)) {
+            Ok(image) => lines.extend(image),
+            Err(error) => lines.push(error.to_string())
+        }
+        lines
+    });
+    return result.map_err(|e| Error::Render(format!("Slideshow failed: {}", e)));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn animate(args: &Args) -> Result<(), Error> {
+    let uri = args.first_input()?;
+    let bytes = input::get_bytes(&uri, &decode_options(args))?;
+    let frames = animation::decode_frames(&bytes).map_err(|e| Error::decode(&uri, e))?;
+    return match frames {
+        Some(frames) if args.frames == Some(Frames::Strip) => print_strip(&uri, &frames, args),
+        Some(frames) => match args.frame {
+            Some(n) if n < frames.len() => print_image(&DynamicImage::ImageRgba8(frames[n].buffer().clone()), args),
+            Some(n) => Err(Error::Usage(format!("Frame {} requested but {} only has {} frames", n, uri, frames.len()))),
+            None => play(&frames, args)
+        },
+        None => match args.frame {
+            Some(n) if n > 0 => Err(Error::Usage(format!("Frame {} requested but {} is not animated", n, uri))),
+            _ => print_image(&input::get_image_from_bytes(bytes, &uri, &decode_options(args))?, args)
+        }
+    }
+}
+
+// Clears the screen once and redraws every frame from the top left corner.
+// Lays out `--frame-count` frames spread evenly over the animation, starting
+// with the first one, like several images.
+fn print_strip(uri: &str, frames: &[Frame], args: &Args) -> Result<(), Error> {
+    let count = (args.frame_count as usize).min(frames.len());
+    let images: Vec<DynamicImage> = (0 .. count)
+        .map(|i| DynamicImage::ImageRgba8(frames[i * frames.len() / count].buffer().clone()))
+        .collect();
+    let lines = render_montage(&vec![uri.to_owned(); count], &images, args)?;
+    return write_lines(&finish(lines, args), args);
+}
+
+// Frames are shown at fixed deadlines, so that the time spent rendering them
+// counts towards their delays. Deadlines that already passed are not caught
+// up with by skipping the frames in between. On a terminal the animation
+// plays on the alternate screen until it ends or q, Esc or Ctrl-C is pressed.
+#[cfg(not(target_arch = "wasm32"))]
+fn play(frames: &[Frame], args: &Args) -> Result<(), Error> {
+    let images: Vec<DynamicImage> = frames.iter()
+        .map(|f| DynamicImage::ImageRgba8(f.buffer().clone()))
+        .collect();
+    let screen = match std::io::stdout().is_terminal() {
+        true => Some(screen::Screen::enter().map_err(|e| Error::Render(format!("Unable to set up the terminal: {}", e)))?),
+        false => None
+    };
+    let written = |e: std::io::Error| Error::Render(format!("Unable to write output: {}", e));
+    let mut redraw = redraw::Redraw::new();
+    let mut deadline = Instant::now();
+    for _ in 0 .. args.loops.unwrap_or(u32::MAX) {
+        for (frame, image) in frames.iter().zip(&images) {
+            let lines = render_image(image, args)?;
+            // Graphics are drawn whole, their cells cannot be compared.
+            let update = if args.is_graphics() { redraw.full(&lines) } else { redraw.update(&lines) };
+            let mut out = std::io::stdout().lock();
+            out.write_all(update.as_bytes()).and_then(|_| out.flush()).map_err(written)?;
+            let delay = match args.fps {
+                Some(fps) => Duration::from_secs_f64(1.0 / fps),
+                None => animation::delay(frame).div_f64(args.speed)
+            };
+            deadline = (deadline + delay).max(Instant::now());
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if screen.is_some() {
+                if screen::wait(remaining).map_err(written)? {
+                    return Ok(());
+                }
+            } else {
+                thread::sleep(remaining);
+            }
+        }
+    }
+    return Ok(());
+}
+
+fn fill(args: &Args) -> Fill {
+    if args.paint == Paint::Bg {
+        return Fill::Char(' ');
+    }
+    return match (args.char, &args.charset) {
+        (Some(c), _) => Fill::Char(c),
+        (None, Some(charset)) => Fill::Ramp(render::ramp(charset)),
+        (None, None) => Fill::Char('$')
+    }
+}
+
+fn parse_image_and_render<P: Pixel<Subpixel: ChannelSum> + 'static, Agg: Aggregator<P> + Clone + IsSame + ToColour, C: Deref<Target = [P::Subpixel]>>(
+    buf: &ImageBuffer<P, C>,
+    zero_agg: Agg,
+    mask: Option<Grid<Coverage>>,
+    args: &Args
+) -> Result<Vec<String>, Error> {
+    let depth = depth(args);
+    let grid = timing::measure(Stage::Aggregate, || aggregate_grid(buf, zero_agg, depth, args));
+    if let Some(path) = &args.save_thumbnail {
+        export::thumbnail(&grid).save(path)
+            .map_err(|e| Error::Render(format!("Unable to save thumbnail to {}: {}", path.display(), e)))?;
+    }
+    match args.output {
+        Output::Json => return Ok(export::json(&grid)),
+        Output::Svg => return Ok(export::svg(&grid, args.x_chunks, args.y_chunks)),
+        Output::Csv => return Ok(export::delimited(&grid, ',')),
+        Output::Tsv => return Ok(export::delimited(&grid, '\t')),
+        Output::Npy => {
+            write_output(&export::npy(&grid), args)?;
+            return Ok(Vec::new());
+        },
+        Output::Ansi | Output::Html => {}
+    }
+    return timing::measure(Stage::Render, || render_grid(&grid, mask.as_ref(), depth, args));
+}
+
+// Draws the chunk colours of `grid` in the mode of the arguments. Chunks that
+// are not opaque in `mask` are left out by the half-block mode.
+fn render_grid(grid: &Grid<Colour>, mask: Option<&Grid<Coverage>>, depth: ColorDepth, args: &Args) -> Result<Vec<String>, Error> {
+    let renderer: Box<dyn Renderer> = match args.mode {
+        Mode::Runs => Box::new(render::Runs { fill: fill(args), paint: args.paint, depth }),
+        Mode::HalfBlock => Box::new(render::HalfBlocks { mask, depth }),
+        Mode::Braille => Box::new(render::Braille { depth }),
+        Mode::Sixel => Box::new(render::Sixel { passthrough: args.passthrough() }),
+        Mode::Edges => Box::new(render::Edges {
+            glyphs: args.edge_glyphs,
+            threshold: args.edge_threshold,
+            depth: if args.edge_color { depth } else { ColorDepth::NoColor },
+        }),
+        Mode::Shades => Box::new(render::Shades { depth }),
+        Mode::Blocks => Box::new(render::Blocks { glyphs: cell_optimizer::glyphs(args.glyphs), depth }),
+        Mode::Threshold => Box::new(render::Threshold { level: args.level, dither: args.dither, depth }),
+        Mode::Ascii => Box::new(render::Ascii { ramp: render::ramp(args.charset.as_deref().unwrap_or(render::DEFAULT_RAMP)) }),
+        Mode::Iterm2 => Box::new(render::Iterm2 { passthrough: args.passthrough() }),
+    };
+    return render::lines(renderer.as_ref(), grid).map_err(|e| Error::Render(format!("Unable to render: {}", e)));
+}
+
+// Samples the chunks of `buf` and applies sharpening, the lookup table, the
+// colour adjustments, effects, theme, posterization and dithering or snapping
+// to the palette to them.
+fn aggregate_grid<P: Pixel<Subpixel: ChannelSum> + 'static, Agg: Aggregator<P> + Clone + ToColour, C: Deref<Target = [P::Subpixel]>>(
+    buf: &ImageBuffer<P, C>,
+    zero_agg: Agg,
+    depth: ColorDepth,
+    args: &Args
+) -> Grid<Colour> {
+    let grid = match args.filter.filter_type() {
+        None => sample_chunks(buf, args.x_chunks, args.y_chunks, zero_agg),
+        Some(filter) => {
+            // One pixel of the resized image per chunk, partial ones included.
+            let n_x = buf.width().div_ceil(args.x_chunks as u32);
+            let n_y = buf.height().div_ceil(args.y_chunks as u32);
+            let resized = imageops::resize(buf, n_x, n_y, filter);
+            sample_chunks(&resized, 1, 1, zero_agg)
+        }
+    };
+    let grid = grid.map(|c| c.to_colour());
+    let grid = match args.sharpen_stage {
+        sharpen::Stage::After if args.sharpen > 0.0 => sharpen::grid(&grid, args.sharpen),
+        _ => grid
+    };
+    let grid = match &args.lut {
+        Some(lut) => grid.map(|c| lut.apply(*c)),
+        None => grid
+    };
+    let adjustments = effects::Adjustments {
+        brightness: args.brightness,
+        contrast: args.contrast,
+        saturation: args.saturation,
+    };
+    let mut grid = grid.map(|c| {
+        let adjusted = effects::adjust(*c, &adjustments);
+        let styled = args.filter_fx.iter().fold(adjusted, |c, e| effects::apply(c, *e));
+        let styled = match args.simulate {
+            Some(deficiency) => effects::simulate(styled, deficiency),
+            None => styled
+        };
+        match args.theme {
+            Some(t) => theme::snap(styled, t, args.color_distance),
+            None => styled
+        }
+    });
+    if let Some(count) = args.posterize {
+        dominant::posterize(&mut grid.cells, count as usize);
+    }
+    match args.dither {
+        // The threshold mode dithers to black and white instead.
+        _ if args.mode == Mode::Threshold => {},
+        palette::Dither::FloydSteinberg => palette::dither(&mut grid.cells, grid.n_x, depth, args.color_distance),
+        palette::Dither::Ordered => palette::dither_ordered(&mut grid.cells, grid.n_x, depth, args.color_distance),
+        palette::Dither::None if args.color_distance != perceptual::Distance::Rgb => palette::snap(&mut grid.cells, depth, args.color_distance),
+        palette::Dither::None => {}
+    }
+    return grid;
+}
+
+fn sample_chunks<P: Pixel<Subpixel: ChannelSum>, Agg: Aggregator<P> + Clone, C: Deref<Target = [P::Subpixel]>>(
+    buf: &ImageBuffer<P, C>,
+    chunks_x: usize,
+    chunks_y: usize,
+    zero_agg: Agg
+) -> Grid<Agg> {
+    let (width, height) = (buf.width() as usize, buf.height() as usize);
+    let n_x = width.div_ceil(chunks_x);
+    let n_y = height.div_ceil(chunks_y);
+
+    let mut rgbs = vec![zero_agg.clone(); n_x * n_y];
+
+    // Chunks at the right and bottom edges cover the remaining pixels, which
+    // may be fewer than those of a whole chunk.
+    for y_chunk in 0 .. n_y {
+        let start = y_chunk * chunks_y;
+        let stop = (start + chunks_y).min(height);
+
+        let slice = &mut rgbs[y_chunk * n_x .. y_chunk * n_x + n_x];
+        for y in start .. stop {
+            sum_chunks_inplace(buf, chunks_x as u32, y as u32, slice);
+        }
+        for (x_chunk, rgb) in slice.iter_mut().enumerate() {
+            let columns = chunks_x.min(width - x_chunk * chunks_x);
+            rgb.div_inplace((columns * (stop - start)) as u64);
+        }
+    }
+    return Grid { n_x, n_y, cells: rgbs };
+}
+
@@ -0,0 +1,147 @@
+use crate::render::rgb_components;
+use ansi_term::Colour;
+use std::fmt;
+use std::sync::Arc;
+
+/// A colour lookup table read from an Adobe/Resolve `.cube` file, either 3D
+/// or one curve per channel.
+pub struct Lut {
+    size: usize,
+    three_d: bool,
+    domain_min: [f64; 3],
+    domain_max: [f64; 3],
+    // Output colours with red changing fastest, then green, then blue.
+    table: Vec<[f64; 3]>,
+}
+
+// The table is too long to print.
+impl fmt::Debug for Lut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "Lut {{ size: {}, three_d: {} }}", self.size, self.three_d);
+    }
+}
+
+fn triple(values: &[&str], line: usize) -> Result<[f64; 3], String> {
+    let parsed: Vec<f64> = values.iter().map(|v| v.parse::<f64>()).collect::<Result<_, _>>()
+        .map_err(|_| format!("Expected three numbers on line {}", line))?;
+    return match parsed[..] {
+        [a, b, c] => Ok([a, b, c]),
+        _ => Err(format!("Expected three numbers on line {}", line))
+    };
+}
+
+/// Parses the text of a `.cube` file.
+pub fn parse(text: &str) -> Result<Lut, String> {
+    let mut lut = Lut { size: 0, three_d: true, domain_min: [0.0; 3], domain_max: [1.0; 3], table: Vec::new() };
+    for (i, line) in text.lines().enumerate() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let size = |w: &[&str]| w.get(1).and_then(|s| s.parse::<usize>().ok()).filter(|n| *n >= 2)
+            .ok_or_else(|| format!("Expected a size of at least 2 on line {}", i + 1));
+        match words.first() {
+            None => {},
+            Some(w) if w.starts_with('#') || *w == "TITLE" => {},
+            Some(&"LUT_3D_SIZE") => lut.size = size(&words)?,
+            Some(&"LUT_1D_SIZE") => {
+                lut.size = size(&words)?;
+                lut.three_d = false;
+            },
+            Some(&"DOMAIN_MIN") => lut.domain_min = triple(&words[1 ..], i + 1)?,
+            Some(&"DOMAIN_MAX") => lut.domain_max = triple(&words[1 ..], i + 1)?,
+            Some(w) if w.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') => lut.table.push(triple(&words, i + 1)?),
+            Some(w) => return Err(format!("Unknown keyword {} on line {}", w, i + 1))
+        }
+    }
+    let expected = if lut.three_d { lut.size.pow(3) } else { lut.size };
+    if lut.size == 0 {
+        return Err(String::from("Missing LUT_3D_SIZE or LUT_1D_SIZE"));
+    }
+    if lut.table.len() != expected {
+        return Err(format!("Expected {} table entries, found {}", expected, lut.table.len()));
+    }
+    return Ok(lut);
+}
+
+/// Reads the `.cube` file at `path`, for `--lut`.
+pub fn load(path: &str) -> Result<Arc<Lut>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Unable to read {}: {}", path, e))?;
+    return parse(&text).map(Arc::new).map_err(|e| format!("{}: {}", path, e));
+}
+
+impl Lut {
+    // Position of `c` in `[0, 1]` within the domain of `channel` scaled to
+    // table indices, split into the index below and the fraction beyond it.
+    fn position(&self, c: f64, channel: usize) -> (usize, f64) {
+        let (min, max) = (self.domain_min[channel], self.domain_max[channel]);
+        let scaled = ((c - min) / (max - min)).clamp(0.0, 1.0) * (self.size - 1) as f64;
+        let index = (scaled.floor() as usize).min(self.size - 2);
+        return (index, scaled - index as f64);
+    }
+
+    fn entry(&self, r: usize, g: usize, b: usize) -> [f64; 3] {
+        return self.table[r + self.size * (g + self.size * b)];
+    }
+
+    /// Looks up an RGB colour, interpolating trilinearly between the
+    /// entries of a 3D table and linearly along the curves of a 1D table.
+    pub fn apply(&self, colour: Colour) -> Colour {
+        let (r, g, b) = rgb_components(&colour);
+        let rgb = [r, g, b].map(|c| c as f64 / 255.0);
+        let graded = if self.three_d {
+            let [(r, fr), (g, fg), (b, fb)] = [0, 1, 2].map(|c| self.position(rgb[c], c));
+            let mut out = [0.0; 3];
+            for (dr, dg, db) in (0 .. 8).map(|i| (i & 1, i >> 1 & 1, i >> 2 & 1)) {
+                let weight = (if dr == 1 { fr } else { 1.0 - fr })
+                    * (if dg == 1 { fg } else { 1.0 - fg })
+                    * (if db == 1 { fb } else { 1.0 - fb });
+                let e = self.entry(r + dr, g + dg, b + db);
+                for c in 0 .. 3 {
+                    out[c] += weight * e[c];
+                }
+            }
+            out
+        } else {
+            [0, 1, 2].map(|c| {
+                let (i, f) = self.position(rgb[c], c);
+                self.table[i][c] * (1.0 - f) + self.table[i + 1][c] * f
+            })
+        };
+        let [r, g, b] = graded.map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8);
+        return Colour::RGB(r, g, b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDENTITY: &str = "LUT_3D_SIZE 2\n0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+
+    #[test]
+    fn identity_keeps_colours() {
+        let lut = parse(IDENTITY).unwrap();
+        for (r, g, b) in [(0, 0, 0), (255, 255, 255), (12, 200, 99), (128, 64, 250)] {
+            assert_eq!(lut.apply(Colour::RGB(r, g, b)), Colour::RGB(r, g, b));
+        }
+    }
+
+    #[test]
+    fn curves_interpolate_per_channel() {
+        let lut = parse("# inverts red\nLUT_1D_SIZE 3\n1 0 0\n0.5 0.5 0.5\n0 1 1\n").unwrap();
+        assert_eq!(lut.apply(Colour::RGB(0, 51, 255)), Colour::RGB(255, 51, 255));
+        assert_eq!(lut.apply(Colour::RGB(255, 0, 0)), Colour::RGB(0, 0, 0));
+    }
+
+    #[test]
+    fn domain_scales_the_input() {
+        let lut = parse(&format!("DOMAIN_MIN 0 0 0\nDOMAIN_MAX 0.5 0.5 0.5\n{}", IDENTITY)).unwrap();
+        assert_eq!(lut.apply(Colour::RGB(64, 128, 255)), Colour::RGB(128, 255, 255));
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        assert!(parse("0 0 0\n").is_err());
+        assert!(parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(parse("LUT_3D_SIZE 2\nLUT_IN_VIDEO_RANGE\n").is_err());
+        assert!(parse("LUT_1D_SIZE 2\n0 0\n1 1 1\n").is_err());
+    }
+}
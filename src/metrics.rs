@@ -0,0 +1,129 @@
+use crate::perceptual::Distance;
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Metric {
+    /// Peak signal to noise ratio in dB over all channels, higher is more similar
+    Psnr,
+    /// Structural similarity of the luminance in windows of 8x8 cells, 1 for identical grids
+    Ssim,
+    /// Mean CIE76 colour difference in Lab, lower is more similar
+    #[value(name = "deltaE")]
+    DeltaE,
+}
+
+impl Metric {
+    /// Whether larger values mean more similar inputs.
+    pub fn higher_is_better(&self) -> bool {
+        return *self != Metric::DeltaE;
+    }
+}
+
+// Side of the square windows over which SSIM statistics are computed.
+const SSIM_WINDOW: usize = 8;
+
+// Constants stabilising the SSIM division for 8 bit values.
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// Similarity of two grids of `width` cells per row, holding the same
+/// number of cells.
+pub fn compute(metric: Metric, a: &[(u8, u8, u8)], b: &[(u8, u8, u8)], width: usize) -> f64 {
+    return match metric {
+        Metric::Psnr => psnr(a, b),
+        Metric::Ssim => ssim(a, b, width),
+        Metric::DeltaE => delta_e(a, b),
+    }
+}
+
+fn psnr(a: &[(u8, u8, u8)], b: &[(u8, u8, u8)]) -> f64 {
+    let squared: f64 = a.iter().zip(b)
+        .map(|(p, q)| [p.0 as f64 - q.0 as f64, p.1 as f64 - q.1 as f64, p.2 as f64 - q.2 as f64])
+        .map(|d| d.iter().map(|c| c * c).sum::<f64>())
+        .sum();
+    let mse = squared / (3 * a.len().max(1)) as f64;
+    if mse == 0.0 {
+        return f64::INFINITY;
+    }
+    return 10.0 * (255.0 * 255.0 / mse).log10();
+}
+
+fn luma(rgb: &(u8, u8, u8)) -> f64 {
+    return 0.299 * rgb.0 as f64 + 0.587 * rgb.1 as f64 + 0.114 * rgb.2 as f64;
+}
+
+// Mean SSIM over non-overlapping windows, the last ones in each direction
+// possibly smaller.
+fn ssim(a: &[(u8, u8, u8)], b: &[(u8, u8, u8)], width: usize) -> f64 {
+    let height = a.len() / width.max(1);
+    let mut total = 0.0;
+    let mut windows = 0;
+    for y0 in (0 .. height).step_by(SSIM_WINDOW) {
+        for x0 in (0 .. width).step_by(SSIM_WINDOW) {
+            let cells: Vec<(f64, f64)> = (y0 .. (y0 + SSIM_WINDOW).min(height))
+                .flat_map(|y| (x0 .. (x0 + SSIM_WINDOW).min(width)).map(move |x| y * width + x))
+                .map(|i| (luma(&a[i]), luma(&b[i])))
+                .collect();
+            let n = cells.len() as f64;
+            let mean_a = cells.iter().map(|c| c.0).sum::<f64>() / n;
+            let mean_b = cells.iter().map(|c| c.1).sum::<f64>() / n;
+            let var_a = cells.iter().map(|c| (c.0 - mean_a).powi(2)).sum::<f64>() / n;
+            let var_b = cells.iter().map(|c| (c.1 - mean_b).powi(2)).sum::<f64>() / n;
+            let covariance = cells.iter().map(|c| (c.0 - mean_a) * (c.1 - mean_b)).sum::<f64>() / n;
+            total += (2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2)
+                / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (var_a + var_b + SSIM_C2));
+            windows += 1;
+        }
+    }
+    return if windows == 0 { 1.0 } else { total / windows as f64 };
+}
+
+fn delta_e(a: &[(u8, u8, u8)], b: &[(u8, u8, u8)]) -> f64 {
+    let total: f64 = a.iter().zip(b).map(|(p, q)| Distance::Cielab.between(*p, *q)).sum();
+    return total / a.len().max(1) as f64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: (u8, u8, u8) = (0, 0, 0);
+    const WHITE: (u8, u8, u8) = (255, 255, 255);
+
+    // A 10x10 grid of varied colours, spanning more than one SSIM window.
+    fn grid() -> Vec<(u8, u8, u8)> {
+        return (0 .. 100).map(|i| ((i * 2) as u8, (255 - i) as u8, (i * 7 % 256) as u8)).collect();
+    }
+
+    #[test]
+    fn identical_grids_are_perfectly_similar() {
+        let a = grid();
+        assert_eq!(compute(Metric::Psnr, &a, &a, 10), f64::INFINITY);
+        assert!((compute(Metric::Ssim, &a, &a, 10) - 1.0).abs() < 1e-12);
+        assert_eq!(compute(Metric::DeltaE, &a, &a, 10), 0.0);
+    }
+
+    #[test]
+    fn black_and_white_are_as_different_as_can_be() {
+        let (a, b) = (vec![BLACK; 16], vec![WHITE; 16]);
+        assert_eq!(compute(Metric::Psnr, &a, &b, 4), 0.0);
+        assert!((compute(Metric::DeltaE, &a, &b, 4) - 100.0).abs() < 0.01);
+        assert!(compute(Metric::Ssim, &a, &b, 4) < 0.01);
+    }
+
+    #[test]
+    fn psnr_falls_with_the_error() {
+        let a = vec![BLACK; 4];
+        let close = compute(Metric::Psnr, &a, &[(1, 1, 1); 4], 2);
+        let far = compute(Metric::Psnr, &a, &[(10, 10, 10); 4], 2);
+        assert!((close - 20.0 * 255f64.log10()).abs() < 1e-9);
+        assert!(far < close);
+    }
+
+    #[test]
+    fn only_delta_e_is_better_when_lower() {
+        assert!(Metric::Psnr.higher_is_better());
+        assert!(Metric::Ssim.higher_is_better());
+        assert!(!Metric::DeltaE.higher_is_better());
+    }
+}
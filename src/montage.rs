@@ -0,0 +1,28 @@
+use crate::render::visible_width;
+
+/// Lays out rendered images in rows of `columns`, padding each image to
+/// `cell_width` terminal columns and separating them by `gutter` spaces.
+pub fn compose(blocks: &[Vec<String>], columns: usize, cell_width: usize, gutter: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (row, images) in blocks.chunks(columns).enumerate() {
+        if row > 0 {
+            lines.push(String::new());
+        }
+        let height = images.iter().map(|b| b.len()).max().unwrap_or(0);
+        for y in 0 .. height {
+            let mut line = String::new();
+            for (i, block) in images.iter().enumerate() {
+                if i > 0 {
+                    line.push_str(&" ".repeat(gutter));
+                }
+                let part = block.get(y).map(String::as_str).unwrap_or("");
+                line.push_str(part);
+                if i + 1 < images.len() {
+                    line.push_str(&" ".repeat(cell_width.saturating_sub(visible_width(part))));
+                }
+            }
+            lines.push(line);
+        }
+    }
+    return lines;
+}
@@ -0,0 +1,76 @@
+// Picks the image a web page is about from its markup, without a full HTML
+// parser: tags are found by name and their attributes split on quotes.
+
+/// Attributes of every `<name ...>` tag in `html`, with lowercase names.
+fn tags(html: &str, name: &str) -> Vec<Vec<(String, String)>> {
+    let lower = html.to_ascii_lowercase();
+    let open = format!("<{}", name);
+    let mut tags = Vec::new();
+    let mut rest = 0;
+    while let Some(start) = lower[rest ..].find(&open).map(|i| rest + i + open.len()) {
+        let end = lower[start ..].find('>').map_or(html.len(), |i| start + i);
+        rest = end;
+        if html[start ..].starts_with(|c: char| c.is_ascii_whitespace()) {
+            tags.push(attributes(&html[start .. end]));
+        }
+    }
+    return tags;
+}
+
+fn attributes(s: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = s.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+    while let Some(eq) = rest.find('=') {
+        let name = rest[.. eq].trim().to_ascii_lowercase();
+        let value = rest[eq + 1 ..].trim_start();
+        let (value, after) = match value.chars().next() {
+            Some(q) if q == '"' || q == '\'' => match value[1 ..].find(q) {
+                Some(end) => (&value[1 .. end + 1], &value[end + 2 ..]),
+                None => (&value[1 ..], "")
+            },
+            _ => value.split_at(value.find(|c: char| c.is_ascii_whitespace()).unwrap_or(value.len()))
+        };
+        // Attributes without a value before this one end up in its name.
+        let name = name.rsplit(|c: char| c.is_ascii_whitespace()).next().unwrap_or_default().to_owned();
+        attributes.push((name, unescape(value)));
+        rest = after.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+    }
+    return attributes;
+}
+
+fn unescape(s: &str) -> String {
+    return s.replace("&quot;", "\"").replace("&#39;", "'").replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&");
+}
+
+fn get<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    return attributes.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+}
+
+/// Whether `bytes` look like an HTML document rather than an image.
+pub fn is_html(bytes: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&bytes[.. bytes.len().min(1024)]).to_ascii_lowercase();
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    return head.starts_with("<!doctype html") || head.starts_with("<html") || head.starts_with("<head");
+}
+
+/// URL, possibly relative, of the image a page announces for previews with
+/// OpenGraph or Twitter card metadata, or otherwise of its largest `<img>`
+/// by the declared size, or its first one if none declares a size.
+pub fn image_url(html: &str) -> Option<String> {
+    let announced = tags(html, "meta").into_iter().find_map(|a| {
+        let kind = get(&a, "property").or(get(&a, "name"))?;
+        let is_preview = ["og:image", "og:image:url", "og:image:secure_url", "twitter:image"].contains(&kind);
+        get(&a, "content").filter(|c| is_preview && !c.is_empty()).map(str::to_owned)
+    });
+    if announced.is_some() {
+        return announced;
+    }
+    let size = |a: &[(String, String)]| {
+        let dimension = |name| get(a, name).and_then(|v| v.trim_end_matches("px").parse::<u64>().ok()).unwrap_or(0);
+        dimension("width") * dimension("height")
+    };
+    let images: Vec<_> = tags(html, "img").into_iter().filter(|a| get(a, "src").is_some_and(|s| !s.is_empty())).collect();
+    // The first of equally large images, `max_by_key` keeps the last.
+    let largest = images.iter().rev().max_by_key(|a| size(a))?;
+    return get(largest, "src").map(str::to_owned);
+}
@@ -0,0 +1,73 @@
+use crate::screen::Screen;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use std::io::{self, Write};
+
+enum Scroll {
+    By(isize),
+    Page(isize),
+    Top,
+    Bottom,
+    Quit,
+    Stay,
+}
+
+fn scroll(key: KeyCode, modifiers: KeyModifiers) -> Scroll {
+    return match key {
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Scroll::Quit,
+        KeyCode::Char('q') | KeyCode::Esc => Scroll::Quit,
+        KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => Scroll::By(1),
+        KeyCode::Char('k') | KeyCode::Up => Scroll::By(-1),
+        KeyCode::Char(' ') | KeyCode::Char('f') | KeyCode::PageDown => Scroll::Page(1),
+        KeyCode::Char('b') | KeyCode::PageUp => Scroll::Page(-1),
+        KeyCode::Char('g') | KeyCode::Home => Scroll::Top,
+        KeyCode::Char('G') | KeyCode::End => Scroll::Bottom,
+        _ => Scroll::Stay
+    }
+}
+
+fn rows() -> usize {
+    return terminal::size().map(|(_, r)| r as usize).unwrap_or(24).max(2);
+}
+
+/// Shows `lines` one screen at a time with a status line below them, like
+/// `less`. `j`/`k` and the arrow keys scroll by a line, space/`b` by a
+/// screen, `g`/`G` jump to the top and bottom and `q`/Esc quits.
+pub fn run(lines: &[String]) -> io::Result<()> {
+    let screen = Screen::enter()?;
+    let result = (|| {
+        let mut top = 0;
+        loop {
+            // The last row holds the status line.
+            let page = rows() - 1;
+            let last_top = lines.len().saturating_sub(page);
+            top = top.min(last_top);
+            let bottom = (top + page).min(lines.len());
+
+            let mut out = io::stdout().lock();
+            write!(out, "\x1b[2J\x1b[H")?;
+            // Raw mode does not translate `\n` into a carriage return.
+            for line in &lines[top .. bottom] {
+                write!(out, "{}\r\n", line)?;
+            }
+            write!(out, "\x1b[7mlines {}-{} of {}  j/k scroll  space/b page  q quit\x1b[0m", top + 1, bottom, lines.len())?;
+            out.flush()?;
+            drop(out);
+
+            let step = match event::read()? {
+                Event::Key(k) if k.kind == KeyEventKind::Press => scroll(k.code, k.modifiers),
+                _ => Scroll::Stay
+            };
+            top = match step {
+                Scroll::By(n) => top.saturating_add_signed(n),
+                Scroll::Page(n) => top.saturating_add_signed(n * page as isize),
+                Scroll::Top => 0,
+                Scroll::Bottom => last_top,
+                Scroll::Stay => top,
+                Scroll::Quit => return Ok(())
+            };
+        }
+    })();
+    drop(screen);
+    return result;
+}
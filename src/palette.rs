@@ -0,0 +1,486 @@
+use ansi_term::{Colour, Style};
+use clap::ValueEnum;
+use crate::perceptual::Distance;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorDepth {
+    /// 24-bit RGB escapes
+    #[value(name = "true")]
+    TrueColor,
+    /// xterm 256 colour palette
+    #[value(name = "256")]
+    Ansi256,
+    /// Standard 16 ANSI colours
+    #[value(name = "16")]
+    Ansi16,
+    /// No escapes at all
+    #[value(skip)]
+    NoColor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Detect colour support from the environment
+    Auto,
+    /// Always emit colour escapes
+    Always,
+    /// Never emit colour escapes
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Dither {
+    /// Every chunk takes the nearest colour
+    None,
+    /// A 4x4 Bayer pattern added to the chunks, which stays in place from frame to frame
+    Ordered,
+    /// Floyd-Steinberg error diffusion to the neighbouring chunks
+    #[value(name = "fs")]
+    FloydSteinberg,
+}
+
+/// 4x4 Bayer threshold matrix, the order in which the cells of a tile are
+/// switched on as a gray gets brighter.
+pub const BAYER: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+
+/// Offset of the chunk in column `x` and row `y` in an ordered dither, from
+/// -0.5 to 0.5 of the step between two levels.
+pub fn bayer(x: usize, y: usize) -> f64 {
+    return (BAYER[y % 4][x % 4] as f64 + 0.5) / 16.0 - 0.5;
+}
+
+fn env_var(name: &str) -> Option<String> {
+    return std::env::var(name).ok().filter(|v| !v.is_empty());
+}
+
+/// Environment variables that advertise or disable colour support.
+#[derive(Clone, Debug, Default)]
+struct Environment {
+    colorterm: Option<String>,
+    term: Option<String>,
+    no_color: bool,
+}
+
+impl Environment {
+    // Empty variables count as unset.
+    fn current() -> Environment {
+        return Environment {
+            colorterm: env_var("COLORTERM"),
+            term: env_var("TERM"),
+            no_color: env_var("NO_COLOR").is_some(),
+        };
+    }
+}
+
+/// Colour depth advertised by `$COLORTERM` and `$TERM`.
+fn detect_depth(env: &Environment) -> ColorDepth {
+    if let Some(colorterm) = &env.colorterm {
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+    }
+    return match &env.term {
+        Some(term) if term == "dumb" => ColorDepth::NoColor,
+        Some(term) if term.contains("truecolor") || term.contains("direct") => ColorDepth::TrueColor,
+        Some(term) if term.contains("256color") => ColorDepth::Ansi256,
+        _ => ColorDepth::Ansi16
+    }
+}
+
+/// Resolves the colour depth to render with. An explicit `depth` takes
+/// precedence over detection unless colour is disabled with `never`;
+/// `auto` also disables colour when the output is not a `terminal` and
+/// honours `$NO_COLOR`.
+pub fn resolve(choice: ColorChoice, depth: Option<ColorDepth>, terminal: bool) -> ColorDepth {
+    return resolve_in(&Environment::current(), choice, depth, terminal);
+}
+
+fn resolve_in(env: &Environment, choice: ColorChoice, depth: Option<ColorDepth>, terminal: bool) -> ColorDepth {
+    return match (choice, depth) {
+        (ColorChoice::Never, _) => ColorDepth::NoColor,
+        (ColorChoice::Auto, _) if !terminal => ColorDepth::NoColor,
+        (_, Some(d)) => d,
+        (ColorChoice::Always, None) => match detect_depth(env) {
+            ColorDepth::NoColor => ColorDepth::TrueColor,
+            d => d
+        },
+        (ColorChoice::Auto, None) if env.no_color => ColorDepth::NoColor,
+        (ColorChoice::Auto, None) => detect_depth(env)
+    }
+}
+
+/// Parses a `#rrggbb` or `rrggbb` hex colour.
+pub fn parse_hex(s: &str) -> Result<Colour, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Expected a colour as #rrggbb: {}", s));
+    }
+    let component = |i: usize| u8::from_str_radix(&hex[i .. i + 2], 16).unwrap_or(0);
+    return Ok(Colour::RGB(component(0), component(2), component(4)));
+}
+
+// Default xterm values of the 16 ANSI colours.
+const ANSI_16: [(u8, u8, u8); 16] = [
+    (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+    (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+    (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+];
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+pub fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    return (dr * dr + dg * dg + db * db) as u32;
+}
+
+fn nearest_cube_level(c: u8) -> usize {
+    return (0 .. CUBE_LEVELS.len())
+        .min_by_key(|i| (CUBE_LEVELS[*i] as i32 - c as i32).abs())
+        .unwrap_or(0);
+}
+
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let (r, g, b) = (nearest_cube_level(rgb.0), nearest_cube_level(rgb.1), nearest_cube_level(rgb.2));
+    let cube = (CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+    let cube_index = 16 + 36 * r + 6 * g + b;
+
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * gray_step;
+    let gray = (gray_level, gray_level, gray_level);
+
+    return if distance(rgb, gray) < distance(rgb, cube) {
+        232 + gray_step
+    } else {
+        cube_index as u8
+    }
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> u8 {
+    return (0 .. ANSI_16.len())
+        .min_by_key(|i| distance(rgb, ANSI_16[*i]))
+        .unwrap_or(0) as u8;
+}
+
+fn ansi_16_colour(index: u8) -> Colour {
+    return match index {
+        0 => Colour::Black,
+        1 => Colour::Red,
+        2 => Colour::Green,
+        3 => Colour::Yellow,
+        4 => Colour::Blue,
+        5 => Colour::Purple,
+        6 => Colour::Cyan,
+        7 => Colour::White,
+        n => Colour::Fixed(n)
+    }
+}
+
+fn palette_256(index: u8) -> (u8, u8, u8) {
+    return match index {
+        0 ..= 15 => ANSI_16[index as usize],
+        16 ..= 231 => {
+            let i = (index - 16) as usize;
+            (CUBE_LEVELS[i / 36], CUBE_LEVELS[i / 6 % 6], CUBE_LEVELS[i % 6])
+        },
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+/// All colours of the palette of `depth`, none for true colour and without
+/// colour.
+pub fn entries(depth: ColorDepth) -> Vec<Colour> {
+    return match depth {
+        ColorDepth::Ansi256 => (0 ..= 255).map(Colour::Fixed).collect(),
+        ColorDepth::Ansi16 => (0 .. 16).map(ansi_16_colour).collect(),
+        ColorDepth::TrueColor | ColorDepth::NoColor => Vec::new()
+    }
+}
+
+/// RGB value shown for `colour` by a terminal using the xterm palette.
+pub fn to_rgb(colour: Colour) -> (u8, u8, u8) {
+    return match colour {
+        Colour::RGB(r, g, b) => (r, g, b),
+        Colour::Fixed(n) => palette_256(n),
+        Colour::Black => ANSI_16[0],
+        Colour::Red => ANSI_16[1],
+        Colour::Green => ANSI_16[2],
+        Colour::Yellow => ANSI_16[3],
+        Colour::Blue => ANSI_16[4],
+        Colour::Purple => ANSI_16[5],
+        Colour::Cyan => ANSI_16[6],
+        Colour::White => ANSI_16[7],
+    }
+}
+
+/// Floyd-Steinberg dithers the row major `colours` of a grid `width` cells
+/// wide to the palette of `depth`. Each colour is replaced by the RGB value of
+/// its palette entry, so that quantizing it again is exact.
+pub fn dither(colours: &mut [Colour], width: usize, depth: ColorDepth, distance: Distance) {
+    if depth == ColorDepth::TrueColor || depth == ColorDepth::NoColor || width == 0 {
+        return;
+    }
+    let mut errors: Vec<[f64; 3]> = colours.iter()
+        .map(|c| {
+            let (r, g, b) = to_rgb(*c);
+            [r as f64, g as f64, b as f64]
+        })
+        .collect();
+    let height = colours.len() / width;
+    for y in 0 .. height {
+        for x in 0 .. width {
+            let i = y * width + x;
+            let wanted = errors[i].map(|c| c.round().clamp(0.0, 255.0) as u8);
+            let shown = to_rgb(quantize_by(Colour::RGB(wanted[0], wanted[1], wanted[2]), depth, distance));
+            colours[i] = Colour::RGB(shown.0, shown.1, shown.2);
+            let error = [
+                errors[i][0] - shown.0 as f64,
+                errors[i][1] - shown.1 as f64,
+                errors[i][2] - shown.2 as f64,
+            ];
+            let mut spread = |dx: isize, dy: usize, weight: f64| {
+                let nx = x as isize + dx;
+                if nx >= 0 && (nx as usize) < width && y + dy < height {
+                    let j = (y + dy) * width + nx as usize;
+                    for c in 0 .. 3 {
+                        errors[j][c] += error[c] * weight;
+                    }
+                }
+            };
+            spread(1, 0, 7.0 / 16.0);
+            spread(-1, 1, 3.0 / 16.0);
+            spread(0, 1, 5.0 / 16.0);
+            spread(1, 1, 1.0 / 16.0);
+        }
+    }
+}
+
+/// Adds the Bayer pattern scaled to the spacing of the palette of `depth`
+/// to the row major `colours` of a grid `width` cells wide and replaces each
+/// by the RGB value of its palette entry, like `dither`.
+pub fn dither_ordered(colours: &mut [Colour], width: usize, depth: ColorDepth, distance: Distance) {
+    let spacing = match depth {
+        ColorDepth::Ansi256 => 48.0,
+        ColorDepth::Ansi16 => 128.0,
+        ColorDepth::TrueColor | ColorDepth::NoColor => return
+    };
+    for (i, colour) in colours.iter_mut().enumerate() {
+        let offset = bayer(i % width.max(1), i / width.max(1)) * spacing;
+        let (r, g, b) = to_rgb(*colour);
+        let shift = |c: u8| (c as f64 + offset).round().clamp(0.0, 255.0) as u8;
+        let shown = to_rgb(quantize_by(Colour::RGB(shift(r), shift(g), shift(b)), depth, distance));
+        *colour = Colour::RGB(shown.0, shown.1, shown.2);
+    }
+}
+
+/// Maps an RGB colour to the closest colour representable at `depth`.
+/// Colours that are not RGB are returned unchanged.
+pub fn quantize(colour: Colour, depth: ColorDepth) -> Colour {
+    let rgb = match colour {
+        Colour::RGB(r, g, b) => (r, g, b),
+        c => return c
+    };
+    return match depth {
+        ColorDepth::TrueColor | ColorDepth::NoColor => colour,
+        ColorDepth::Ansi256 => Colour::Fixed(nearest_256(rgb)),
+        ColorDepth::Ansi16 => ansi_16_colour(nearest_16(rgb)),
+    }
+}
+
+/// Maps an RGB colour to the colour representable at `depth` that is
+/// closest by `distance`. The 256 colour palette is searched without the 16
+/// ANSI colours, which terminals change, as `quantize` does.
+pub fn quantize_by(colour: Colour, depth: ColorDepth, distance: Distance) -> Colour {
+    let rgb = match colour {
+        Colour::RGB(r, g, b) if distance != Distance::Rgb => (r, g, b),
+        c => return quantize(c, depth)
+    };
+    let entries = match depth {
+        ColorDepth::Ansi256 => (16 ..= 255).map(Colour::Fixed).collect(),
+        d => entries(d)
+    };
+    return entries.into_iter()
+        .map(|entry| (entry, distance.between(rgb, to_rgb(entry))))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(colour, |(entry, _)| entry);
+}
+
+/// Replaces each of the `colours` by the RGB value of the colour of `depth`
+/// closest by `distance`, so that quantizing it again is exact.
+pub fn snap(colours: &mut [Colour], depth: ColorDepth, distance: Distance) {
+    if depth == ColorDepth::TrueColor || depth == ColorDepth::NoColor {
+        return;
+    }
+    let mut snapped = HashMap::new();
+    for colour in colours.iter_mut() {
+        let (r, g, b) = *snapped.entry(to_rgb(*colour)).or_insert_with(|| to_rgb(quantize_by(*colour, depth, distance)));
+        *colour = Colour::RGB(r, g, b);
+    }
+}
+
+/// Style painting text in `colour` at `depth`.
+pub fn foreground(colour: Colour, depth: ColorDepth) -> Style {
+    return match depth {
+        ColorDepth::NoColor => Style::new(),
+        d => quantize(colour, d).normal()
+    }
+}
+
+/// Style painting the background of text in `colour` at `depth`.
+pub fn background(colour: Colour, depth: ColorDepth) -> Style {
+    return match depth {
+        ColorDepth::NoColor => Style::new(),
+        d => Style::new().on(quantize(colour, d))
+    }
+}
+
+/// Style painting text in `fg` on a `bg` background at `depth`.
+pub fn on(fg: Colour, bg: Colour, depth: ColorDepth) -> Style {
+    return match depth {
+        ColorDepth::NoColor => Style::new(),
+        d => quantize(fg, d).on(quantize(bg, d))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(colorterm: Option<&str>, term: Option<&str>, no_color: bool) -> Environment {
+        return Environment { colorterm: colorterm.map(str::to_owned), term: term.map(str::to_owned), no_color };
+    }
+
+    #[test]
+    fn detects_the_depth_advertised_by_the_terminal() {
+        let auto = |e: &Environment| resolve_in(e, ColorChoice::Auto, None, true);
+        assert_eq!(auto(&env(Some("truecolor"), Some("xterm"), false)), ColorDepth::TrueColor);
+        assert_eq!(auto(&env(Some("24bit"), None, false)), ColorDepth::TrueColor);
+        assert_eq!(auto(&env(None, Some("xterm-direct"), false)), ColorDepth::TrueColor);
+        assert_eq!(auto(&env(None, Some("xterm-256color"), false)), ColorDepth::Ansi256);
+        assert_eq!(auto(&env(Some("yes"), Some("screen-256color"), false)), ColorDepth::Ansi256);
+        assert_eq!(auto(&env(None, Some("xterm"), false)), ColorDepth::Ansi16);
+        assert_eq!(auto(&env(None, None, false)), ColorDepth::Ansi16);
+        assert_eq!(auto(&env(None, Some("dumb"), false)), ColorDepth::NoColor);
+    }
+
+    #[test]
+    fn auto_disables_colour_for_no_color_and_pipes() {
+        assert_eq!(resolve_in(&env(Some("truecolor"), Some("xterm-256color"), true), ColorChoice::Auto, None, true), ColorDepth::NoColor);
+        assert_eq!(resolve_in(&env(Some("truecolor"), None, false), ColorChoice::Auto, None, false), ColorDepth::NoColor);
+        assert_eq!(resolve_in(&env(None, None, false), ColorChoice::Auto, Some(ColorDepth::Ansi256), false), ColorDepth::NoColor);
+    }
+
+    #[test]
+    fn explicit_depths_override_detection() {
+        let environments = [
+            env(Some("truecolor"), None, false),
+            env(None, Some("xterm-256color"), false),
+            env(None, Some("dumb"), false),
+            env(None, Some("xterm"), true),
+        ];
+        for e in &environments {
+            for depth in [ColorDepth::TrueColor, ColorDepth::Ansi256, ColorDepth::Ansi16] {
+                assert_eq!(resolve_in(e, ColorChoice::Always, Some(depth), false), depth, "{:?}", e);
+                assert_eq!(resolve_in(e, ColorChoice::Never, Some(depth), true), ColorDepth::NoColor, "{:?}", e);
+            }
+        }
+        // Explicit depths override NO_COLOR in a terminal, as --colors asks for colour.
+        assert_eq!(resolve_in(&environments[3], ColorChoice::Auto, Some(ColorDepth::Ansi16), true), ColorDepth::Ansi16);
+    }
+
+    #[test]
+    fn always_colours_even_without_support() {
+        assert_eq!(resolve_in(&env(None, Some("dumb"), true), ColorChoice::Always, None, false), ColorDepth::TrueColor);
+        assert_eq!(resolve_in(&env(None, Some("xterm-256color"), true), ColorChoice::Always, None, false), ColorDepth::Ansi256);
+        assert_eq!(resolve_in(&env(Some("truecolor"), None, false), ColorChoice::Never, None, true), ColorDepth::NoColor);
+    }
+
+    #[test]
+    fn cube_colours_map_to_their_xterm_indices() {
+        assert_eq!(quantize(Colour::RGB(0, 0, 0), ColorDepth::Ansi256), Colour::Fixed(16));
+        assert_eq!(quantize(Colour::RGB(255, 0, 0), ColorDepth::Ansi256), Colour::Fixed(196));
+        assert_eq!(quantize(Colour::RGB(0, 255, 0), ColorDepth::Ansi256), Colour::Fixed(46));
+        assert_eq!(quantize(Colour::RGB(0, 0, 255), ColorDepth::Ansi256), Colour::Fixed(21));
+        assert_eq!(quantize(Colour::RGB(255, 255, 255), ColorDepth::Ansi256), Colour::Fixed(231));
+        assert_eq!(quantize(Colour::RGB(95, 135, 175), ColorDepth::Ansi256), Colour::Fixed(67));
+    }
+
+    #[test]
+    fn grays_map_to_the_gray_ramp() {
+        assert_eq!(quantize(Colour::RGB(8, 8, 8), ColorDepth::Ansi256), Colour::Fixed(232));
+        assert_eq!(quantize(Colour::RGB(128, 128, 128), ColorDepth::Ansi256), Colour::Fixed(244));
+        assert_eq!(quantize(Colour::RGB(238, 238, 238), ColorDepth::Ansi256), Colour::Fixed(255));
+        // Grays on the cube stay there.
+        assert_eq!(quantize(Colour::RGB(95, 95, 95), ColorDepth::Ansi256), Colour::Fixed(59));
+    }
+
+    #[test]
+    fn colours_map_to_the_nearest_ansi_colour() {
+        assert_eq!(quantize(Colour::RGB(0, 0, 0), ColorDepth::Ansi16), Colour::Black);
+        assert_eq!(quantize(Colour::RGB(200, 10, 10), ColorDepth::Ansi16), Colour::Red);
+        assert_eq!(quantize(Colour::RGB(255, 0, 0), ColorDepth::Ansi16), Colour::Fixed(9));
+        assert_eq!(quantize(Colour::RGB(130, 125, 128), ColorDepth::Ansi16), Colour::Fixed(8));
+        assert_eq!(quantize(Colour::RGB(255, 255, 255), ColorDepth::Ansi16), Colour::Fixed(15));
+    }
+
+    #[test]
+    fn quantized_colours_quantize_to_themselves() {
+        // The 16 ANSI colours are left out of the 256 colour search.
+        let entries = (16 ..= 255).map(Colour::Fixed).map(|e| (e, ColorDepth::Ansi256))
+            .chain(entries(ColorDepth::Ansi16).into_iter().map(|e| (e, ColorDepth::Ansi16)));
+        for (entry, depth) in entries {
+            let (r, g, b) = to_rgb(entry);
+            assert_eq!(quantize(Colour::RGB(r, g, b), depth), entry);
+        }
+        let mut rng = fastrand::Rng::with_seed(1);
+        for _ in 0 .. 1000 {
+            let colour = Colour::RGB(rng.u8(..), rng.u8(..), rng.u8(..));
+            for depth in [ColorDepth::Ansi256, ColorDepth::Ansi16] {
+                let (r, g, b) = to_rgb(quantize(colour, depth));
+                assert_eq!(quantize(Colour::RGB(r, g, b), depth), quantize(colour, depth));
+            }
+        }
+    }
+
+    #[test]
+    fn snapped_colours_stay_put_when_snapped_again() {
+        let mut colours = vec![Colour::RGB(12, 200, 99), Colour::RGB(128, 128, 128), Colour::RGB(250, 3, 160)];
+        snap(&mut colours, ColorDepth::Ansi256, Distance::Rgb);
+        let once = colours.clone();
+        snap(&mut colours, ColorDepth::Ansi256, Distance::Rgb);
+        assert_eq!(colours, once);
+    }
+
+    // A gray between the ANSI grays (127, 127, 127) and (229, 229, 229) is
+    // drawn with a mix of both.
+    #[test]
+    fn ordered_dithering_mixes_the_neighbouring_levels() {
+        let mut colours = vec![Colour::RGB(170, 170, 170); 16];
+        dither_ordered(&mut colours, 4, ColorDepth::Ansi16, Distance::Rgb);
+        let dark = colours.iter().filter(|c| **c == Colour::RGB(127, 127, 127)).count();
+        let light = colours.iter().filter(|c| **c == Colour::RGB(229, 229, 229)).count();
+        assert!(dark > 0 && light > 0, "{:?}", colours);
+        assert_eq!(dark + light, 16);
+        assert!(dark > light);
+    }
+
+    #[test]
+    fn bayer_offsets_are_centred() {
+        let offsets: Vec<f64> = (0 .. 16).map(|i| bayer(i % 4, i / 4)).collect();
+        assert!(offsets.iter().all(|o| (-0.5 .. 0.5).contains(o)));
+        assert!(offsets.iter().sum::<f64>().abs() < 1e-12);
+        assert_eq!(bayer(1, 2), bayer(5, 6));
+    }
+}
@@ -0,0 +1,21 @@
+use image::DynamicImage;
+use pdfium_render::prelude::*;
+
+// Resolution pages are rendered at, in pixels per inch.
+const DPI: f32 = 150.0;
+
+/// Renders the page with the zero-based `index` of a PDF document through
+/// the pdfium library installed on the system.
+pub fn rasterize(data: &[u8], index: usize) -> Result<DynamicImage, String> {
+    let bindings = Pdfium::bind_to_system_library().map_err(|e| format!("Unable to load pdfium: {}", e))?;
+    let pdfium = Pdfium::new(bindings);
+    let document = pdfium.load_pdf_from_byte_slice(data, None).map_err(|e| e.to_string())?;
+    let pages = document.pages();
+    if index >= pages.len() as usize {
+        return Err(format!("Page {} requested but the document only has {} pages", index + 1, pages.len()));
+    }
+    let page = pages.get(index as PdfPageIndex).map_err(|e| e.to_string())?;
+    let config = PdfRenderConfig::new().scale_page_by_factor(DPI / 72.0);
+    let bitmap = page.render_with_config(&config).map_err(|e| e.to_string())?;
+    return Ok(bitmap.as_image());
+}
@@ -0,0 +1,154 @@
+use crate::aggregate::srgb_to_linear;
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Distance {
+    /// Euclidean distance of the sRGB components
+    Rgb,
+    /// Euclidean distance in CIELAB, also known as CIE76
+    Cielab,
+    /// CIEDE2000 colour difference, which corrects CIELAB for blues, grays and saturated colours
+    Ciede2000,
+    /// Euclidean distance in OKLab
+    Oklab,
+}
+
+fn linear(rgb: (u8, u8, u8)) -> [f64; 3] {
+    return [srgb_to_linear(rgb.0), srgb_to_linear(rgb.1), srgb_to_linear(rgb.2)];
+}
+
+/// CIELAB coordinates of an sRGB colour under the D65 white point, with L
+/// from 0 to 100.
+pub fn lab(rgb: (u8, u8, u8)) -> [f64; 3] {
+    let [r, g, b] = linear(rgb);
+    let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+    let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+    let z = (0.0193339 * r + 0.1191920 * g + 0.9503041 * b) / 1.08883;
+    let f = |t: f64| if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    return [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)];
+}
+
+/// OKLab coordinates of linear light RGB, with L from 0 to 1.
+pub fn oklab_from_linear([r, g, b]: [f64; 3]) -> [f64; 3] {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    return [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ];
+}
+
+/// Linear light RGB of OKLab coordinates, outside of `[0, 1]` for colours
+/// outside of the sRGB gamut.
+pub fn linear_from_oklab([l, a, b]: [f64; 3]) -> [f64; 3] {
+    let l_ = (l + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m_ = (l - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s_ = (l - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    return [
+        4.0767416621 * l_ - 3.3077115913 * m_ + 0.2309699292 * s_,
+        -1.2684380046 * l_ + 2.6097574011 * m_ - 0.3413193965 * s_,
+        -0.0041960863 * l_ - 0.7034186147 * m_ + 1.7076147010 * s_,
+    ];
+}
+
+fn euclidean(a: [f64; 3], b: [f64; 3]) -> f64 {
+    return ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt();
+}
+
+// CIEDE2000 difference of two CIELAB colours, after Sharma, Wu and Dalal,
+// "The CIEDE2000 color-difference formula", 2005, with unit weights.
+fn ciede2000([l1, a1, b1]: [f64; 3], [l2, a2, b2]: [f64; 3]) -> f64 {
+    let c_mean = (a1.hypot(b1) + a2.hypot(b2)) / 2.0;
+    let g = 0.5 * (1.0 - (c_mean.powi(7) / (c_mean.powi(7) + 25f64.powi(7))).sqrt());
+    let (a1, a2) = (a1 * (1.0 + g), a2 * (1.0 + g));
+    let (c1, c2) = (a1.hypot(b1), a2.hypot(b2));
+    let hue = |a: f64, b: f64| if a == 0.0 && b == 0.0 { 0.0 } else { b.atan2(a).to_degrees().rem_euclid(360.0) };
+    let (h1, h2) = (hue(a1, b1), hue(a2, b2));
+
+    let dl = l2 - l1;
+    let dc = c2 - c1;
+    let dh = match h2 - h1 {
+        _ if c1 * c2 == 0.0 => 0.0,
+        d if d > 180.0 => d - 360.0,
+        d if d < -180.0 => d + 360.0,
+        d => d
+    };
+    let dh = 2.0 * (c1 * c2).sqrt() * (dh / 2.0).to_radians().sin();
+
+    let l_mean = (l1 + l2) / 2.0;
+    let c_mean = (c1 + c2) / 2.0;
+    let h_mean = match (h1 + h2, (h1 - h2).abs()) {
+        (sum, _) if c1 * c2 == 0.0 => sum,
+        (sum, d) if d <= 180.0 => sum / 2.0,
+        (sum, _) if sum < 360.0 => (sum + 360.0) / 2.0,
+        (sum, _) => (sum - 360.0) / 2.0
+    };
+    let t = 1.0 - 0.17 * (h_mean - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_mean).to_radians().cos()
+        + 0.32 * (3.0 * h_mean + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_mean - 63.0).to_radians().cos();
+    let sl = 1.0 + 0.015 * (l_mean - 50.0).powi(2) / (20.0 + (l_mean - 50.0).powi(2)).sqrt();
+    let sc = 1.0 + 0.045 * c_mean;
+    let sh = 1.0 + 0.015 * c_mean * t;
+    let rotation = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+    let rc = 2.0 * (c_mean.powi(7) / (c_mean.powi(7) + 25f64.powi(7))).sqrt();
+    let rt = -(2.0 * rotation).to_radians().sin() * rc;
+    return ((dl / sl).powi(2) + (dc / sc).powi(2) + (dh / sh).powi(2) + rt * (dc / sc) * (dh / sh)).sqrt();
+}
+
+impl Distance {
+    /// Difference between two sRGB colours, in the units of the metric.
+    pub fn between(&self, a: (u8, u8, u8), b: (u8, u8, u8)) -> f64 {
+        let rgb = |c: (u8, u8, u8)| [c.0 as f64, c.1 as f64, c.2 as f64];
+        return match self {
+            Distance::Rgb => euclidean(rgb(a), rgb(b)),
+            Distance::Cielab => euclidean(lab(a), lab(b)),
+            Distance::Ciede2000 => ciede2000(lab(a), lab(b)),
+            Distance::Oklab => euclidean(oklab_from_linear(linear(a)), oklab_from_linear(linear(b))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ciede2000_matches_published_pairs() {
+        // Pairs 1, 7, 17 and 25 of the test data of Sharma, Wu and Dalal.
+        let pairs = [
+            ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+            ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+            ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+            ([60.2574, -34.0099, 36.2677], [60.4626, -34.1751, 39.4387], 1.2644),
+        ];
+        for (a, b, expected) in pairs {
+            assert!((ciede2000(a, b) - expected).abs() < 1e-4, "{:?} {:?}", a, b);
+            assert!((ciede2000(b, a) - expected).abs() < 1e-4, "{:?} {:?}", b, a);
+        }
+    }
+
+    #[test]
+    fn lab_of_white_and_black() {
+        let white = lab((255, 255, 255));
+        assert!((white[0] - 100.0).abs() < 1e-3 && white[1].abs() < 1e-3 && white[2].abs() < 1e-3);
+        assert_eq!(lab((0, 0, 0)), [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn oklab_of_white() {
+        let white = oklab_from_linear([1.0, 1.0, 1.0]);
+        assert!((white[0] - 1.0).abs() < 1e-4 && white[1].abs() < 1e-4 && white[2].abs() < 1e-4);
+    }
+
+    #[test]
+    fn oklab_round_trips() {
+        for rgb in [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.2, 0.5, 0.9], [0.05, 0.8, 0.3]] {
+            let back = linear_from_oklab(oklab_from_linear(rgb));
+            assert!((0 .. 3).all(|c| (back[c] - rgb[c]).abs() < 1e-6), "{:?} {:?}", rgb, back);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use fastrand::Rng;
+use std::sync::Mutex;
+
+// The generator behind every random choice, so that a single --seed makes
+// all of them reproducible. Seeded from the system unless seeded explicitly.
+static RNG: Mutex<Option<Rng>> = Mutex::new(None);
+
+/// Restarts the random numbers from `seed`, or from a random seed if omitted.
+pub fn seed(seed: Option<u64>) {
+    let rng = match seed {
+        Some(seed) => Rng::with_seed(seed),
+        None => Rng::new()
+    };
+    *RNG.lock().unwrap_or_else(|e| e.into_inner()) = Some(rng);
+}
+
+/// Calls `f` with the generator.
+pub fn with<T>(f: impl FnOnce(&mut Rng) -> T) -> T {
+    let mut rng = RNG.lock().unwrap_or_else(|e| e.into_inner());
+    return f(rng.get_or_insert_with(Rng::new));
+}
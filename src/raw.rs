@@ -0,0 +1,43 @@
+// Camera RAW files carry a full size or nearly full size JPEG rendition of
+// the photo for the camera display, next to the sensor data and a small
+// thumbnail. Rather than developing the sensor data, the largest of these
+// JPEGs is shown.
+
+const SOI: [u8; 3] = [0xFF, 0xD8, 0xFF];
+
+fn u16_at(data: &[u8], i: usize) -> Option<u16> {
+    return Some(u16::from_be_bytes([*data.get(i)?, *data.get(i + 1)?]));
+}
+
+// Width and height of the baseline or progressive JPEG at the start of
+// `data`, found by walking its segments up to the frame header. Other frame
+// types, like the lossless JPEG holding the sensor data of some formats, are
+// rejected.
+fn jpeg_dimensions(data: &[u8]) -> Option<(u16, u16)> {
+    let mut i = 2;
+    loop {
+        if *data.get(i)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(i + 1)?;
+        match marker {
+            0xC0 ..= 0xC2 => return Some((u16_at(data, i + 7)?, u16_at(data, i + 5)?)),
+            0xC3 | 0xC5 ..= 0xC7 | 0xC9 ..= 0xCB | 0xCD ..= 0xCF | 0xDA => return None,
+            _ => i += 2 + u16_at(data, i + 2)? as usize
+        }
+    }
+}
+
+/// Offset of the JPEG preview with the most pixels embedded in `data`.
+pub fn preview_offset(data: &[u8]) -> Option<usize> {
+    let mut best: Option<(u32, usize)> = None;
+    for start in data.windows(SOI.len()).enumerate().filter(|(_, w)| *w == SOI).map(|(i, _)| i) {
+        if let Some((width, height)) = jpeg_dimensions(&data[start ..]) {
+            let pixels = width as u32 * height as u32;
+            if best.is_none_or(|(most, _)| pixels > most) {
+                best = Some((pixels, start));
+            }
+        }
+    }
+    return best.map(|(_, start)| start);
+}
@@ -0,0 +1,162 @@
+/// A character on screen together with the SGR escapes in effect for it.
+#[derive(Clone, Debug, PartialEq)]
+struct Cell {
+    style: String,
+    glyph: char,
+}
+
+// Splits a rendered line into cells. Escapes moving the cursor forward leave
+// blank cells, and a reset clears the style.
+fn cells(line: &str) -> Vec<Cell> {
+    let mut cells = Vec::new();
+    let mut style = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            cells.push(Cell { style: style.clone(), glyph: c });
+            continue;
+        }
+        let mut escape = String::from(c);
+        for c in chars.by_ref() {
+            escape.push(c);
+            if c.is_ascii_alphabetic() {
+                break;
+            }
+        }
+        let params = escape.trim_start_matches("\x1b[");
+        match params.chars().last() {
+            Some('m') if params == "m" || params == "0m" => style.clear(),
+            Some('m') => style.push_str(&escape),
+            Some('C') => {
+                let n = params.trim_end_matches('C').parse().unwrap_or(1);
+                cells.extend(std::iter::repeat_n(Cell { style: String::new(), glyph: ' ' }, n));
+            },
+            _ => {}
+        }
+    }
+    return cells;
+}
+
+/// Frames drawn from the top left corner of the screen, each updating only
+/// the cells that differ from the frame before, which takes a fraction of the
+/// bytes of a full redraw when little changes, as in most animations.
+pub struct Redraw {
+    previous: Vec<Vec<Cell>>,
+}
+
+impl Redraw {
+    pub fn new() -> Redraw {
+        return Redraw { previous: Vec::new() };
+    }
+
+    /// Forgets the frame on screen, so that the next one is drawn in full on
+    /// a cleared screen, as after the terminal was resized.
+    pub fn reset(&mut self) {
+        self.previous.clear();
+    }
+
+    /// Escapes and text drawing `lines` in full from the top left corner,
+    /// for frames whose cells cannot be told apart, like sixel and iTerm2
+    /// images. The cursor is left below them.
+    pub fn full(&mut self, lines: &[String]) -> String {
+        self.reset();
+        let mut out = String::from("\x1b[H");
+        for line in lines {
+            out.push_str(line);
+            out.push_str("\r\n");
+        }
+        out.push_str("\x1b[J");
+        return out;
+    }
+
+    /// Escapes and text turning the previous frame on screen into `lines`,
+    /// leaving the cursor below them. Frames holding DCS or OSC sequences,
+    /// as graphics are sent, are drawn in full.
+    pub fn update(&mut self, lines: &[String]) -> String {
+        if lines.iter().any(|l| l.contains("\x1bP") || l.contains("\x1b]")) {
+            return self.full(lines);
+        }
+        let mut out = String::new();
+        if self.previous.is_empty() {
+            out.push_str("\x1b[2J");
+        }
+        let frame: Vec<Vec<Cell>> = lines.iter().map(|l| cells(l)).collect();
+        for (y, row) in frame.iter().enumerate() {
+            let old = self.previous.get(y).map(Vec::as_slice).unwrap_or(&[]);
+            let mut x = 0;
+            while x < row.len() {
+                if old.get(x) == Some(&row[x]) {
+                    x += 1;
+                    continue;
+                }
+                out.push_str(&format!("\x1b[{};{}H", y + 1, x + 1));
+                let mut style: Option<&str> = None;
+                while x < row.len() && old.get(x) != Some(&row[x]) {
+                    if style != Some(row[x].style.as_str()) {
+                        out.push_str("\x1b[0m");
+                        out.push_str(&row[x].style);
+                        style = Some(row[x].style.as_str());
+                    }
+                    out.push(row[x].glyph);
+                    x += 1;
+                }
+                out.push_str("\x1b[0m");
+            }
+            if row.len() < old.len() {
+                out.push_str(&format!("\x1b[{};{}H\x1b[K", y + 1, row.len() + 1));
+            }
+        }
+        if frame.len() < self.previous.len() {
+            out.push_str(&format!("\x1b[{};1H\x1b[J", frame.len() + 1));
+        }
+        out.push_str(&format!("\x1b[{};1H", frame.len() + 1));
+        self.previous = frame;
+        return out;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(lines: &[&str]) -> Vec<String> {
+        return lines.iter().map(|l| l.to_string()).collect();
+    }
+
+    #[test]
+    fn first_frames_are_drawn_on_a_cleared_screen() {
+        let mut redraw = Redraw::new();
+        assert_eq!(redraw.update(&frame(&["ab"])), "\x1b[2J\x1b[1;1H\x1b[0mab\x1b[0m\x1b[2;1H");
+    }
+
+    #[test]
+    fn unchanged_frames_only_move_the_cursor() {
+        let mut redraw = Redraw::new();
+        redraw.update(&frame(&["\x1b[31mab", "cd"]));
+        assert_eq!(redraw.update(&frame(&["\x1b[31mab", "cd"])), "\x1b[3;1H");
+    }
+
+    #[test]
+    fn changed_cells_are_written_in_place() {
+        let mut redraw = Redraw::new();
+        redraw.update(&frame(&["abc", "def"]));
+        assert_eq!(redraw.update(&frame(&["abc", "dxf"])), "\x1b[2;2H\x1b[0mx\x1b[0m\x1b[3;1H");
+    }
+
+    #[test]
+    fn shorter_frames_clear_what_is_left() {
+        let mut redraw = Redraw::new();
+        redraw.update(&frame(&["abc", "def", "ghi"]));
+        assert_eq!(redraw.update(&frame(&["ab"])), "\x1b[1;3H\x1b[K\x1b[2;1H\x1b[J\x1b[2;1H");
+    }
+
+    #[test]
+    fn graphics_are_passed_through_intact() {
+        let sixel = "\x1bP0;1;0q\"1;1;1;6#0;2;100;100;100#0~-\x1b\\";
+        let iterm2 = "\x1b]1337;File=inline=1:AAAA\x07";
+        let mut redraw = Redraw::new();
+        redraw.update(&frame(&["ab"]));
+        assert_eq!(redraw.update(&frame(&[sixel])), format!("\x1b[H{}\r\n\x1b[J", sixel));
+        assert_eq!(redraw.update(&frame(&[iterm2])), format!("\x1b[H{}\r\n\x1b[J", iterm2));
+    }
+}
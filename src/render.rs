@@ -0,0 +1,498 @@
+pub use crate::aggregate::Coverage;
+use crate::cell_optimizer;
+pub use crate::cell_optimizer::{glyphs as block_glyphs, Glyphs as BlockGlyphs};
+use crate::edges;
+pub use crate::edges::Glyphs as EdgeGlyphs;
+use crate::export;
+use crate::iterm2;
+use crate::palette;
+pub use crate::palette::{ColorDepth, Dither};
+use crate::shade;
+use crate::sixel;
+use crate::threshold;
+use crate::tmux;
+use crate::{Grid, IsSame, ToColour};
+use ansi_term::{Colour, Style};
+use clap::ValueEnum;
+use image::{DynamicImage, ImageError};
+use std::fmt::Write;
+use std::io;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Paint {
+    /// Colour the glyphs
+    Fg,
+    /// Colour the background of spaces, drawing solid blocks
+    Bg,
+}
+
+// A run of adjacent cells sharing colour and glyph.
+struct RgbCount {
+    colour: Colour,
+    glyph: char,
+    count: usize
+}
+
+impl RgbCount {
+    fn is_same_rgb(&self, colour: &Colour, glyph: char) -> bool {
+        return self.colour.is_same(colour) && self.glyph == glyph
+    }
+
+    fn incr(&mut self) {
+        self.count += 1
+    }
+
+    fn is_valid(&self) -> bool {
+        self.count > 0
+    }
+
+    fn set_rgb(&mut self, colour: Colour, glyph: char) {
+        self.colour = colour;
+        self.glyph = glyph;
+        self.count = 1;
+    }
+
+    fn invalid() -> RgbCount {
+        RgbCount {
+            colour: Colour::Black,
+            glyph: ' ',
+            count: 0
+        }
+    }
+
+    // Appends the escapes and glyphs of the run to `line`, without building
+    // an intermediate string for the glyphs.
+    fn paint(&self, line: &mut String, paint: Paint, depth: ColorDepth) {
+        let style = match paint {
+            Paint::Fg => palette::foreground(self.colour, depth),
+            Paint::Bg => palette::background(self.colour, depth)
+        };
+        let _ = write!(line, "{}", style.prefix());
+        line.extend(std::iter::repeat_n(self.glyph, self.count));
+        let _ = write!(line, "{}", style.suffix());
+    }
+}
+
+/// Glyphs drawn by the runs mode: either a single fill character or a ramp
+/// indexed by chunk luminance.
+pub enum Fill {
+    Char(char),
+    Ramp(Vec<char>)
+}
+
+impl Fill {
+    fn glyph(&self, colour: &Colour) -> char {
+        return match self {
+            Fill::Char(c) => *c,
+            Fill::Ramp(ramp) => ramp_glyph(ramp, colour)
+        }
+    }
+}
+
+pub const DEFAULT_RAMP: &str = " .:-=+*#%@";
+
+/// Glyphs of a non-empty `charset`, checked when parsing the arguments.
+pub fn ramp(charset: &str) -> Vec<char> {
+    return charset.chars().collect();
+}
+
+fn ramp_glyph(ramp: &[char], colour: &Colour) -> char {
+    let idx = (luminance(colour) / 255.0 * (ramp.len() - 1) as f64).round() as usize;
+    return ramp[idx.min(ramp.len() - 1)];
+}
+
+pub fn rgb_components(colour: &Colour) -> (u8, u8, u8) {
+    return match colour {
+        Colour::RGB(r, g, b) => (*r, *g, *b),
+        _ => (0, 0, 0)
+    }
+}
+
+pub fn luminance(colour: &Colour) -> f64 {
+    let (r, g, b) = rgb_components(colour);
+    return 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+}
+
+/// Number of terminal columns in `line`, ignoring SGR escape sequences.
+pub fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in line.chars() {
+        match (in_escape, c) {
+            (false, '\x1b') => in_escape = true,
+            (false, _) => width += 1,
+            (true, 'm') => in_escape = false,
+            (true, _) => {}
+        }
+    }
+    return width;
+}
+
+/// Backend drawing a grid of chunk colours for the terminal.
+pub trait Renderer {
+    /// Writes the lines drawing `grid` to `out`, each followed by `\n`.
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()>;
+}
+
+// Writes `lines` to `out`, each followed by a line break.
+fn write_lines(out: &mut dyn io::Write, lines: Vec<String>) -> io::Result<()> {
+    for line in lines {
+        out.write_all(line.as_bytes())?;
+        out.write_all(b"\n")?;
+    }
+    return Ok(());
+}
+
+/// Lines `renderer` draws `grid` with, without their line breaks.
+pub fn lines(renderer: &dyn Renderer, grid: &Grid<Colour>) -> io::Result<Vec<String>> {
+    let mut out = Vec::new();
+    renderer.render(grid, &mut out)?;
+    let text = String::from_utf8(out).map_err(io::Error::other)?;
+    return Ok(text.lines().map(String::from).collect());
+}
+
+/// Runs of `fill` glyphs with a single colour escape for each run of chunks
+/// with the same colour.
+pub struct Runs {
+    pub fill: Fill,
+    pub paint: Paint,
+    pub depth: ColorDepth,
+}
+
+impl Renderer for Runs {
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()> {
+        return write_lines(out, runs(grid, &self.fill, self.paint, self.depth));
+    }
+}
+
+/// Two chunks per cell drawn with half blocks, leaving the chunks that are
+/// not opaque in `mask` transparent.
+pub struct HalfBlocks<'a> {
+    pub mask: Option<&'a Grid<Coverage>>,
+    pub depth: ColorDepth,
+}
+
+impl Renderer for HalfBlocks<'_> {
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()> {
+        let mut cells = grid.map(|c| Some(palette::quantize(*c, self.depth)));
+        if let Some(mask) = self.mask {
+            for (cell, coverage) in cells.cells.iter_mut().zip(&mask.cells) {
+                if !coverage.opaque {
+                    *cell = None;
+                }
+            }
+        }
+        return write_lines(out, half_blocks(&cells, self.depth));
+    }
+}
+
+/// 2x4 chunks per cell drawn as braille dots.
+pub struct Braille {
+    pub depth: ColorDepth,
+}
+
+impl Renderer for Braille {
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()> {
+        return write_lines(out, braille(grid, self.depth));
+    }
+}
+
+/// A block of pixels per chunk in DEC sixel graphics, wrapped for tmux
+/// with `passthrough`.
+pub struct Sixel {
+    pub passthrough: bool,
+}
+
+impl Renderer for Sixel {
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()> {
+        let lines = sixel(grid);
+        return write_lines(out, if self.passthrough { lines.iter().map(|l| tmux::wrap(l)).collect() } else { lines });
+    }
+}
+
+/// Edges in 2x4 chunks per cell drawn with `glyphs`, see `edges::edges`.
+pub struct Edges {
+    pub glyphs: EdgeGlyphs,
+    pub threshold: f64,
+    pub depth: ColorDepth,
+}
+
+impl Renderer for Edges {
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()> {
+        return write_lines(out, edges::edges(grid, self.glyphs, self.threshold, self.depth));
+    }
+}
+
+/// Shade blocks mixing two palette colours in every cell, see `shade::cell`.
+pub struct Shades {
+    pub depth: ColorDepth,
+}
+
+impl Renderer for Shades {
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()> {
+        return write_lines(out, shades(grid, self.depth));
+    }
+}
+
+/// Block glyphs covering `cell_optimizer::CELL` chunks per cell, each
+/// picked from `glyphs` with the colours that draw its chunks best.
+pub struct Blocks {
+    pub glyphs: Vec<(char, u16)>,
+    pub depth: ColorDepth,
+}
+
+impl Renderer for Blocks {
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()> {
+        let cells = cell_optimizer::optimize(grid, &self.glyphs);
+        return write_lines(out, (0 .. cells.n_y).map(|y| {
+            let mut line = String::new();
+            for (glyph, fg, bg) in cells.row(y) {
+                let style = if fg == bg { palette::foreground(*fg, self.depth) } else { palette::on(*fg, *bg, self.depth) };
+                let _ = write!(line, "{}{}{}", style.prefix(), glyph, style.suffix());
+            }
+            line
+        }).collect());
+    }
+}
+
+/// One pixel per chunk in an image through the iTerm2 inline image
+/// protocol, scaled by the terminal to a cell per chunk and wrapped for tmux
+/// with `passthrough`.
+pub struct Iterm2 {
+    pub passthrough: bool,
+}
+
+impl Renderer for Iterm2 {
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()> {
+        let image = DynamicImage::ImageRgb8(export::thumbnail(grid));
+        return write_lines(out, iterm2(&image, grid.n_x, self.passthrough).map_err(io::Error::other)?);
+    }
+}
+
+/// Black and white chunks, two per cell, see `threshold::binarize`.
+pub struct Threshold {
+    pub level: f64,
+    pub dither: Dither,
+    pub depth: ColorDepth,
+}
+
+impl Renderer for Threshold {
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()> {
+        return write_lines(out, threshold::lines(&threshold::binarize(grid, self.level, self.dither), self.depth));
+    }
+}
+
+/// Uncoloured glyphs of `ramp` picked by chunk luminance.
+pub struct Ascii {
+    pub ramp: Vec<char>,
+}
+
+impl Renderer for Ascii {
+    fn render(&self, grid: &Grid<Colour>, out: &mut dyn io::Write) -> io::Result<()> {
+        return write_lines(out, ascii(grid, &self.ramp));
+    }
+}
+
+pub(crate) fn runs<Agg: ToColour>(grid: &Grid<Agg>, fill: &Fill, paint: Paint, depth: ColorDepth) -> Vec<String> {
+    let mut lines = Vec::with_capacity(grid.n_y);
+    for y_chunk in 0 .. grid.n_y {
+        let mut line = String::new();
+        let mut run = RgbCount::invalid();
+        for rgb in grid.row(y_chunk) {
+            let colour = rgb.to_colour();
+            let glyph = fill.glyph(&colour);
+            let colour = palette::quantize(colour, depth);
+            if run.is_same_rgb(&colour, glyph) {
+                run.incr();
+            } else {
+                if run.is_valid() {
+                    run.paint(&mut line, paint, depth);
+                }
+                run.set_rgb(colour, glyph);
+            }
+        }
+        run.paint(&mut line, paint, depth);
+        lines.push(line);
+    }
+    return lines;
+}
+
+// Each terminal row covers two chunk rows: the upper chunk is painted as the
+// foreground of `▀` and the lower chunk as its background. Missing chunks,
+// either transparent or below an odd last row, keep the terminal's default
+// background, and a cell with only a lower chunk is drawn as `▄`.
+pub(crate) fn half_blocks(grid: &Grid<Option<Colour>>, depth: ColorDepth) -> Vec<String> {
+    let mut lines = Vec::with_capacity(grid.n_y.div_ceil(2));
+    for y_chunk in (0 .. grid.n_y).step_by(2) {
+        let upper = grid.row(y_chunk);
+        let lower = if y_chunk + 1 < grid.n_y { Some(grid.row(y_chunk + 1)) } else { None };
+        let mut line = String::new();
+        for x in 0 .. grid.n_x {
+            let cell = match (upper[x], lower.and_then(|l| l[x])) {
+                (Some(u), Some(l)) => palette::on(u, l, depth).paint("\u{2580}"),
+                (Some(u), None) => palette::foreground(u, depth).paint("\u{2580}"),
+                (None, Some(l)) => palette::foreground(l, depth).paint("\u{2584}"),
+                (None, None) => Style::new().paint(" ")
+            };
+            let _ = write!(line, "{}", cell);
+        }
+        lines.push(line);
+    }
+    return lines;
+}
+
+// Bit of the U+2800 braille block for the dot in column `x` and row `y` of a cell.
+pub const BRAILLE_DOTS: [[u32; 2]; 4] = [
+    [0x01, 0x08],
+    [0x02, 0x10],
+    [0x04, 0x20],
+    [0x40, 0x80],
+];
+
+// Each terminal cell covers 2x4 chunks. A dot is raised if its chunk is at
+// least as bright as the cell average, and the glyph is painted with the
+// average colour of all chunks in the cell.
+pub(crate) fn braille<Agg: ToColour>(grid: &Grid<Agg>, depth: ColorDepth) -> Vec<String> {
+    let mut lines = Vec::with_capacity(grid.n_y.div_ceil(4));
+    for y_cell in (0 .. grid.n_y).step_by(4) {
+        let mut line = String::new();
+        for x_cell in (0 .. grid.n_x).step_by(2) {
+            let mut colours = Vec::with_capacity(8);
+            for dy in 0 .. 4 {
+                for dx in 0 .. 2 {
+                    let (x, y) = (x_cell + dx, y_cell + dy);
+                    if x < grid.n_x && y < grid.n_y {
+                        colours.push((dx, dy, grid.row(y)[x].to_colour()));
+                    }
+                }
+            }
+            let n = colours.len() as u32;
+            let (mut r, mut g, mut b) = (0u32, 0u32, 0u32);
+            for (_, _, c) in &colours {
+                let (cr, cg, cb) = rgb_components(c);
+                r += cr as u32;
+                g += cg as u32;
+                b += cb as u32;
+            }
+            let average = Colour::RGB((r / n) as u8, (g / n) as u8, (b / n) as u8);
+            let threshold = luminance(&average);
+            let mut bits = 0;
+            for (dx, dy, c) in &colours {
+                if luminance(c) >= threshold {
+                    bits |= BRAILLE_DOTS[*dy][*dx];
+                }
+            }
+            let glyph = char::from_u32(0x2800 + bits).unwrap_or(' ');
+            let style = palette::foreground(average, depth);
+            let _ = write!(line, "{}{}{}", style.prefix(), glyph, style.suffix());
+        }
+        lines.push(line);
+    }
+    return lines;
+}
+
+// Size in pixels of the block drawn for each chunk, roughly one terminal cell.
+const SIXEL_CELL_WIDTH: u32 = 8;
+const SIXEL_CELL_HEIGHT: u32 = 16;
+
+pub(crate) fn sixel<Agg: ToColour>(grid: &Grid<Agg>) -> Vec<String> {
+    let colours: Vec<Colour> = grid.cells.iter().map(|c| c.to_colour()).collect();
+    let width = grid.n_x as u32 * SIXEL_CELL_WIDTH;
+    let height = grid.n_y as u32 * SIXEL_CELL_HEIGHT;
+    let data = sixel::encode(width, height, |x, y| {
+        let idx = (y / SIXEL_CELL_HEIGHT) as usize * grid.n_x + (x / SIXEL_CELL_WIDTH) as usize;
+        rgb_components(&colours[idx])
+    });
+    return vec![data];
+}
+
+pub(crate) fn iterm2(image: &DynamicImage, columns: usize, passthrough: bool) -> Result<Vec<String>, ImageError> {
+    return iterm2::encode(image, columns).map(|data| vec![if passthrough { tmux::wrap(&data) } else { data }]);
+}
+
+pub(crate) fn shades<Agg: ToColour>(grid: &Grid<Agg>, depth: ColorDepth) -> Vec<String> {
+    let mut lines = Vec::with_capacity(grid.n_y);
+    for y_chunk in 0 .. grid.n_y {
+        let mut line = String::new();
+        for rgb in grid.row(y_chunk) {
+            let (glyph, fg, bg) = shade::cell(rgb.to_colour(), depth);
+            let style = if fg == bg { palette::foreground(fg, depth) } else { palette::on(fg, bg, depth) };
+            let _ = write!(line, "{}{}{}", style.prefix(), glyph, style.suffix());
+        }
+        lines.push(line);
+    }
+    return lines;
+}
+
+pub(crate) fn ascii<Agg: ToColour>(grid: &Grid<Agg>, ramp: &[char]) -> Vec<String> {
+    return (0 .. grid.n_y)
+        .map(|y_chunk| grid.row(y_chunk).iter()
+            .map(|c| ramp_glyph(ramp, &c.to_colour()))
+            .collect())
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 4x4 grid of a red to blue gradient over a dark to bright one.
+    fn grid() -> Grid<Colour> {
+        let cells = (0 .. 16).map(|i| Colour::RGB((i % 4 * 80) as u8, (i / 4 * 80) as u8, 255 - (i % 4 * 80) as u8)).collect();
+        return Grid::new(4, 4, cells).unwrap();
+    }
+
+    // Every backend with the number of lines it draws the grid of `grid` in.
+    fn backends() -> Vec<(&'static str, Box<dyn Renderer>, usize)> {
+        let depth = ColorDepth::TrueColor;
+        return vec![
+            ("runs", Box::new(Runs { fill: Fill::Char('$'), paint: Paint::Fg, depth }), 4),
+            ("half blocks", Box::new(HalfBlocks { mask: None, depth }), 2),
+            ("braille", Box::new(Braille { depth }), 1),
+            ("sixel", Box::new(Sixel { passthrough: false }), 1),
+            ("iterm2", Box::new(Iterm2 { passthrough: false }), 1),
+            ("edges", Box::new(Edges { glyphs: EdgeGlyphs::Braille, threshold: 0.2, depth }), 1),
+            ("shades", Box::new(Shades { depth }), 4),
+            ("blocks", Box::new(Blocks { glyphs: block_glyphs(BlockGlyphs::Quadrants), depth }), 1),
+            ("threshold", Box::new(Threshold { level: 0.5, dither: Dither::None, depth }), 2),
+            ("ascii", Box::new(Ascii { ramp: ramp(DEFAULT_RAMP) }), 4),
+        ];
+    }
+
+    #[test]
+    fn every_backend_writes_whole_lines() {
+        for (name, renderer, rows) in backends() {
+            let mut out = Vec::new();
+            renderer.render(&grid(), &mut out).unwrap();
+            let text = String::from_utf8(out).unwrap();
+            assert!(text.ends_with('\n'), "{}", name);
+            assert_eq!(text.matches('\n').count(), rows, "{}", name);
+            assert_eq!(lines(renderer.as_ref(), &grid()).unwrap().join("\n") + "\n", text, "{}", name);
+        }
+    }
+
+    #[test]
+    fn every_backend_is_deterministic() {
+        for (name, renderer, _) in backends() {
+            assert_eq!(lines(renderer.as_ref(), &grid()).unwrap(), lines(renderer.as_ref(), &grid()).unwrap(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn every_backend_draws_empty_grids_as_nothing() {
+        let empty = Grid::new(0, 0, Vec::new()).unwrap();
+        for (name, renderer, _) in backends().into_iter().filter(|(name, _, _)| !matches!(*name, "sixel" | "iterm2")) {
+            assert!(lines(renderer.as_ref(), &empty).unwrap().is_empty(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn character_backends_span_the_grid() {
+        // Columns of the 4 chunk wide grid in cells, as the escapes are skipped.
+        for (name, renderer, _) in backends().into_iter().filter(|(name, _, _)| !matches!(*name, "sixel" | "iterm2")) {
+            let widths: Vec<usize> = lines(renderer.as_ref(), &grid()).unwrap().iter().map(|l| visible_width(l)).collect();
+            let expected = if matches!(name, "braille" | "edges" | "blocks") { 2 } else { 4 };
+            assert!(widths.iter().all(|w| *w == expected), "{} {:?}", name, widths);
+        }
+    }
+}
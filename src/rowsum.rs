@@ -0,0 +1,117 @@
+use image::Primitive;
+
+/// Subpixel types whose runs of interleaved pixels can be summed per channel.
+pub trait ChannelSum: Primitive + Into<u64> {
+    /// Sums of the first three channels over `subpixels`, a run of whole
+    /// pixels with `channels` subpixels each.
+    fn sum_channels(subpixels: &[Self], channels: usize) -> [u64; 3] {
+        return sum_scalar(subpixels, channels);
+    }
+}
+
+/// Sums of the first three channels like `ChannelSum::sum_channels`, one
+/// pixel at a time.
+pub fn sum_scalar<T: Primitive + Into<u64>>(subpixels: &[T], channels: usize) -> [u64; 3] {
+    let mut sum = [0u64; 3];
+    for pixel in subpixels.chunks_exact(channels) {
+        sum[0] += pixel[0].into();
+        sum[1] += pixel[1].into();
+        sum[2] += pixel[2].into();
+    }
+    return sum;
+}
+
+#[cfg(not(feature = "simd"))]
+impl ChannelSum for u8 {}
+
+#[cfg(feature = "simd")]
+impl ChannelSum for u8 {
+    fn sum_channels(subpixels: &[u8], channels: usize) -> [u64; 3] {
+        return simd::sum_channels(subpixels, channels);
+    }
+}
+
+impl ChannelSum for u16 {}
+
+impl ChannelSum for u32 {}
+
+#[cfg(feature = "simd")]
+mod simd {
+    use wide::{u16x8, u8x16};
+
+    // Blocks of 16 pixels are loaded as `C` vectors of 16 bytes. Lane `j` of
+    // the `k`-th half vector then always holds channel `(8k + j) % C`, so the
+    // lanes can be accumulated without shuffling.
+    const PIXELS_PER_BLOCK: usize = 16;
+    // u16 lanes hold the sum of 256 bytes before they overflow.
+    const BLOCKS_PER_FLUSH: usize = 256;
+
+    pub fn sum_channels(subpixels: &[u8], channels: usize) -> [u64; 3] {
+        return match channels {
+            3 => sum_blocks::<3, 6>(subpixels),
+            4 => sum_blocks::<4, 8>(subpixels),
+            _ => super::sum_scalar(subpixels, channels),
+        };
+    }
+
+    // `H` is the number of half vectors in a block, `2 * C`.
+    fn sum_blocks<const C: usize, const H: usize>(subpixels: &[u8]) -> [u64; 3] {
+        let block = PIXELS_PER_BLOCK * C;
+        let blocks = subpixels.len() / block;
+        let mut sum = [0u64; 3];
+        for group in subpixels[.. blocks * block].chunks(block * BLOCKS_PER_FLUSH) {
+            let mut lanes = [u16x8::ZERO; H];
+            for b in group.chunks_exact(block) {
+                for k in 0 .. C {
+                    let bytes: [u8; 16] = b[16 * k .. 16 * (k + 1)].try_into().unwrap_or([0; 16]);
+                    let v = u8x16::new(bytes);
+                    lanes[2 * k] += u16x8::from_u8x16_low(v);
+                    lanes[2 * k + 1] += u16x8::from_u8x16_high(v);
+                }
+            }
+            for (k, lane) in lanes.iter().enumerate() {
+                for (j, value) in lane.to_array().iter().enumerate() {
+                    let channel = (8 * k + j) % C;
+                    if channel < 3 {
+                        sum[channel] += *value as u64;
+                    }
+                }
+            }
+        }
+        let rest = super::sum_scalar(&subpixels[blocks * block ..], C);
+        return [sum[0] + rest[0], sum[1] + rest[1], sum[2] + rest[2]];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scalar_sums_skip_alpha() {
+        assert_eq!(sum_scalar(&[1u8, 2, 3, 255, 4, 5, 6, 255], 4), [5, 7, 9]);
+        assert_eq!(sum_scalar(&[1u16, 2, 3, 4, 5, 6], 3), [5, 7, 9]);
+    }
+
+    // Rows of random pixels, of lengths around and between the blocks of
+    // 16 pixels and beyond the 256 blocks after which the lanes are flushed.
+    #[test]
+    fn channel_sums_match_the_scalar_sums() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for channels in [3, 4] {
+            for pixels in [0, 1, 15, 16, 17, 31, 33, 250, 1001, 16 * 256 + 7, 3 * 16 * 256 + 100, 70_000] {
+                let mut row = vec![0u8; pixels * channels];
+                rng.fill(&mut row);
+                assert_eq!(u8::sum_channels(&row, channels), sum_scalar(&row, channels), "{} pixels of {} channels", pixels, channels);
+            }
+        }
+    }
+
+    #[test]
+    fn channel_sums_of_white_do_not_overflow() {
+        for channels in [3, 4] {
+            let row = vec![255u8; 16 * 256 * 3 * channels];
+            assert_eq!(u8::sum_channels(&row, channels), [255 * 16 * 256 * 3; 3]);
+        }
+    }
+}
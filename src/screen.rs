@@ -0,0 +1,55 @@
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// The alternate screen of the terminal in raw mode with a hidden cursor,
+/// which keeps the scrollback of the main screen intact. Everything is
+/// restored when the guard is dropped, also while unwinding from a panic.
+pub struct Screen;
+
+impl Screen {
+    pub fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        let screen = Screen;
+        let mut out = io::stdout();
+        write!(out, "\x1b[?1049h\x1b[?25l")?;
+        out.flush()?;
+        return Ok(screen);
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let mut out = io::stdout();
+        let _ = write!(out, "\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = out.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Whether the key pressed is one that quits, `q`, Esc or Ctrl-C.
+pub fn is_quit(key: KeyCode, modifiers: KeyModifiers) -> bool {
+    return match key {
+        KeyCode::Char('c') => modifiers.contains(KeyModifiers::CONTROL),
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        _ => false
+    };
+}
+
+/// Waits for `timeout`, or until a key that quits is pressed, which returns
+/// `true`. Other input is discarded.
+pub fn wait(timeout: Duration) -> io::Result<bool> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if !event::poll(remaining)? {
+            return Ok(false);
+        }
+        if let Event::Key(k) = event::read()? {
+            if k.kind == KeyEventKind::Press && is_quit(k.code, k.modifiers) {
+                return Ok(true);
+            }
+        }
+    }
+}
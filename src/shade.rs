@@ -0,0 +1,48 @@
+use crate::aggregate::{linear_to_srgb, srgb_to_linear};
+use crate::palette::{self, ColorDepth};
+use crate::render::{luminance, rgb_components};
+use ansi_term::Colour;
+
+/// Shade blocks by the share of the cell their foreground covers.
+const SHADES: [(char, f64); 3] = [('░', 0.25), ('▒', 0.5), ('▓', 0.75)];
+
+const FULL: char = '█';
+
+fn linear(rgb: (u8, u8, u8)) -> [f64; 3] {
+    return [srgb_to_linear(rgb.0), srgb_to_linear(rgb.1), srgb_to_linear(rgb.2)];
+}
+
+/// Glyph, foreground and background approximating `colour` at `depth`. The
+/// foreground is the palette colour closest to `colour`, and a shade block
+/// mixes it with the background that brings the mix, as the eye sees it in
+/// linear light, closest to `colour`. True colour needs no mixing, and
+/// without colours the shades stand for the luminance alone.
+pub fn cell(colour: Colour, depth: ColorDepth) -> (char, Colour, Colour) {
+    match depth {
+        ColorDepth::TrueColor => return (FULL, colour, colour),
+        ColorDepth::NoColor => {
+            let glyph = match (luminance(&colour) / 255.0 * 4.0).round() as usize {
+                0 => ' ',
+                n if n > SHADES.len() => FULL,
+                n => SHADES[n - 1].0
+            };
+            return (glyph, colour, colour);
+        },
+        ColorDepth::Ansi256 | ColorDepth::Ansi16 => {}
+    }
+    let wanted = rgb_components(&colour);
+    let fg = palette::quantize(colour, depth);
+    let mut best = (FULL, fg, fg, palette::distance(wanted, palette::to_rgb(fg)));
+    let fg_light = linear(palette::to_rgb(fg));
+    for bg in palette::entries(depth) {
+        let bg_light = linear(palette::to_rgb(bg));
+        for (glyph, coverage) in SHADES {
+            let mix = [0, 1, 2].map(|c| linear_to_srgb(fg_light[c] * coverage + bg_light[c] * (1.0 - coverage)));
+            let error = palette::distance(wanted, (mix[0], mix[1], mix[2]));
+            if error < best.3 {
+                best = (glyph, fg, bg, error);
+            }
+        }
+    }
+    return (best.0, best.1, best.2);
+}
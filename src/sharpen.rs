@@ -0,0 +1,61 @@
+use crate::Grid;
+use crate::render::rgb_components;
+use ansi_term::Colour;
+use clap::ValueEnum;
+use image::imageops;
+use image::{Rgb, RgbImage};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Stage {
+    /// Sharpen the image before it is sampled, emphasizing detail about the size of a chunk
+    Before,
+    /// Sharpen the sampled chunk colours against their neighbours
+    After,
+}
+
+fn clamp(c: f64) -> u8 {
+    return c.round().clamp(0.0, 255.0) as u8;
+}
+
+// Adds `amount` times the difference between `original` and `blurred`.
+fn unsharp(original: [u8; 3], blurred: [f64; 3], amount: f64) -> [u8; 3] {
+    return [0, 1, 2].map(|c| clamp(original[c] as f64 + amount * (original[c] as f64 - blurred[c])));
+}
+
+/// Unsharp mask of `buf` sampled in chunks of `x_chunks` x `y_chunks`
+/// pixels. Sharpening only the finest detail would be averaged away by the
+/// chunks, so the blur subtracted is about as wide as half a chunk.
+pub fn image(buf: &RgbImage, amount: f64, x_chunks: usize, y_chunks: usize) -> RgbImage {
+    let sigma = (x_chunks.max(y_chunks) as f32 / 2.0).max(0.5);
+    let blurred = imageops::fast_blur(buf, sigma);
+    return RgbImage::from_fn(buf.width(), buf.height(), |x, y| {
+        let b = blurred.get_pixel(x, y).0;
+        Rgb(unsharp(buf.get_pixel(x, y).0, b.map(f64::from), amount))
+    });
+}
+
+/// Unsharp mask of the chunk colours of `grid`, with each chunk blurred with
+/// its eight neighbours weighted 1-2-1 along both axes.
+pub fn grid(grid: &Grid<Colour>, amount: f64) -> Grid<Colour> {
+    let rgb = |x: usize, y: usize| {
+        let (r, g, b) = rgb_components(&grid.cells[y * grid.n_x + x]);
+        [r as f64, g as f64, b as f64]
+    };
+    let cells = (0 .. grid.n_y).flat_map(|y| (0 .. grid.n_x).map(move |x| (x, y))).map(|(x, y)| {
+        let mut blurred = [0.0; 3];
+        let mut total = 0.0;
+        for ny in y.saturating_sub(1) ..= (y + 1).min(grid.n_y - 1) {
+            for nx in x.saturating_sub(1) ..= (x + 1).min(grid.n_x - 1) {
+                let weight = (if nx == x { 2.0 } else { 1.0 }) * (if ny == y { 2.0 } else { 1.0 });
+                let c = rgb(nx, ny);
+                for i in 0 .. 3 {
+                    blurred[i] += c[i] * weight;
+                }
+                total += weight;
+            }
+        }
+        let [r, g, b] = unsharp(rgb(x, y).map(clamp), blurred.map(|c| c / total), amount);
+        Colour::RGB(r, g, b)
+    }).collect();
+    return Grid { n_x: grid.n_x, n_y: grid.n_y, cells };
+}
@@ -0,0 +1,138 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+// Sixel terminals commonly support 256 colour registers.
+const MAX_REGISTERS: usize = 256;
+
+fn reduce(rgb: (u8, u8, u8)) -> (u8, u8, u8) {
+    return (rgb.0 & 0xe0, rgb.1 & 0xe0, rgb.2 & 0xc0);
+}
+
+fn percent(c: u8) -> u32 {
+    return (c as u32 * 100 + 127) / 255;
+}
+
+/// Encodes a `width` x `height` image as a DEC sixel sequence.
+///
+/// Images with more distinct colours than there are colour registers are
+/// reduced to a 3-3-2 bit palette first.
+pub fn encode<F: Fn(u32, u32) -> (u8, u8, u8)>(width: u32, height: u32, pixel: F) -> String {
+    let mut palette: HashMap<(u8, u8, u8), usize> = HashMap::new();
+    let mut indices = vec![0usize; width as usize * height as usize];
+    let mut colours = Vec::with_capacity(indices.len());
+    for y in 0 .. height {
+        for x in 0 .. width {
+            colours.push(pixel(x, y));
+        }
+    }
+
+    let distinct = colours.iter().collect::<HashSet<_>>().len();
+    if distinct > MAX_REGISTERS {
+        for c in colours.iter_mut() {
+            *c = reduce(*c);
+        }
+    }
+    for (i, c) in colours.iter().enumerate() {
+        let n = palette.len();
+        indices[i] = *palette.entry(*c).or_insert(n);
+    }
+
+    let mut out = String::new();
+    out.push_str("\x1bP0;1;0q");
+    let _ = write!(out, "\"1;1;{};{}", width, height);
+    let mut registers: Vec<_> = palette.iter().collect();
+    registers.sort_by_key(|(_, i)| **i);
+    for (c, i) in registers {
+        let _ = write!(out, "#{};2;{};{};{}", i, percent(c.0), percent(c.1), percent(c.2));
+    }
+
+    for band in (0 .. height).step_by(6) {
+        let rows = (height - band).min(6);
+        let mut used: Vec<usize> = Vec::new();
+        for y in band .. band + rows {
+            for x in 0 .. width {
+                let idx = indices[(y * width + x) as usize];
+                if !used.contains(&idx) {
+                    used.push(idx);
+                }
+            }
+        }
+        for (n, register) in used.iter().enumerate() {
+            if n > 0 {
+                out.push('$');
+            }
+            let _ = write!(out, "#{}", register);
+            let mut run_char = '?';
+            let mut run_length = 0;
+            for x in 0 .. width {
+                let mut bits = 0u8;
+                for dy in 0 .. rows {
+                    if indices[((band + dy) * width + x) as usize] == *register {
+                        bits |= 1 << dy;
+                    }
+                }
+                let c = (0x3f + bits) as char;
+                if c == run_char {
+                    run_length += 1;
+                } else {
+                    push_run(&mut out, run_char, run_length);
+                    run_char = c;
+                    run_length = 1;
+                }
+            }
+            push_run(&mut out, run_char, run_length);
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    return out;
+}
+
+fn push_run(out: &mut String, c: char, n: usize) {
+    match n {
+        0 => {},
+        1 ..= 3 => out.extend(std::iter::repeat_n(c, n)),
+        _ => { let _ = write!(out, "!{}{}", n, c); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: (u8, u8, u8) = (255, 255, 255);
+    const RED: (u8, u8, u8) = (255, 0, 0);
+    const BLUE: (u8, u8, u8) = (0, 0, 255);
+
+    #[test]
+    fn one_column_fills_one_sixel() {
+        assert_eq!(encode(1, 6, |_, _| WHITE), "\x1bP0;1;0q\"1;1;1;6#0;2;100;100;100#0~-\x1b\\");
+    }
+
+    // The top three rows are red and the rest blue, so the first band draws
+    // both colours over each other and the second one only blue.
+    #[test]
+    fn bands_draw_each_colour_in_turn() {
+        let encoded = encode(2, 12, |_, y| if y < 3 { RED } else { BLUE });
+        assert_eq!(encoded, "\x1bP0;1;0q\"1;1;2;12#0;2;100;0;0#1;2;0;0;100#0FF$#1ww-#1~~-\x1b\\");
+    }
+
+    #[test]
+    fn partial_bands_leave_the_missing_rows_empty() {
+        assert_eq!(encode(1, 7, |_, _| WHITE), "\x1bP0;1;0q\"1;1;1;7#0;2;100;100;100#0~-#0@-\x1b\\");
+    }
+
+    #[test]
+    fn runs_of_more_than_three_are_repeated() {
+        assert_eq!(encode(3, 6, |_, _| WHITE), "\x1bP0;1;0q\"1;1;3;6#0;2;100;100;100#0~~~-\x1b\\");
+        assert_eq!(encode(20, 6, |_, _| WHITE), "\x1bP0;1;0q\"1;1;20;6#0;2;100;100;100#0!20~-\x1b\\");
+        assert_eq!(encode(6, 6, |x, _| if x < 4 { RED } else { BLUE }), "\x1bP0;1;0q\"1;1;6;6#0;2;100;0;0#1;2;0;0;100#0!4~??$#1!4?~~-\x1b\\");
+    }
+
+    #[test]
+    fn too_many_colours_are_reduced_to_3_3_2_bits() {
+        let encoded = encode(32, 32, |x, y| ((x * 8) as u8, (y * 8) as u8, 0));
+        assert_eq!(encoded.matches(";2;").count(), 8 * 8);
+        assert!(encoded.contains("#9;2;13;13;0"));
+    }
+}
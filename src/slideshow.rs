@@ -0,0 +1,67 @@
+use crate::screen::Screen;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+enum Step {
+    Next,
+    Previous,
+    Quit,
+    Redraw,
+    Stay,
+}
+
+fn step(key: KeyCode, modifiers: KeyModifiers) -> Step {
+    return match key {
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Step::Quit,
+        KeyCode::Char('q') | KeyCode::Esc => Step::Quit,
+        KeyCode::Right | KeyCode::Down | KeyCode::Char('n') | KeyCode::Char(' ') => Step::Next,
+        KeyCode::Left | KeyCode::Up | KeyCode::Char('p') | KeyCode::Backspace => Step::Previous,
+        _ => Step::Stay
+    }
+}
+
+/// Shows the lines rendered by `show` for each of `count` images, advancing
+/// every `interval`. Right/`n`/space and left/`p` move between images and
+/// `q`/Esc quits. The current image is rendered again when the terminal is
+/// resized, without restarting its interval.
+pub fn run<F: FnMut(usize) -> Vec<String>>(count: usize, interval: Duration, mut show: F) -> io::Result<()> {
+    let screen = Screen::enter()?;
+    let result = (|| {
+        let mut index = 0;
+        let mut shown = Instant::now();
+        loop {
+            let mut out = io::stdout().lock();
+            write!(out, "\x1b[2J\x1b[H")?;
+            // Raw mode does not translate `\n` into a carriage return.
+            for line in show(index) {
+                write!(out, "{}\r\n", line)?;
+            }
+            out.flush()?;
+            drop(out);
+
+            let next = loop {
+                let step = match event::poll(interval.saturating_sub(shown.elapsed()))? {
+                    true => match event::read()? {
+                        Event::Key(k) if k.kind == KeyEventKind::Press => step(k.code, k.modifiers),
+                        Event::Resize(_, _) => Step::Redraw,
+                        _ => Step::Stay
+                    },
+                    false => Step::Next
+                };
+                if !matches!(step, Step::Stay) {
+                    break step;
+                }
+            };
+            index = match next {
+                Step::Next => (index + 1) % count,
+                Step::Previous => (index + count - 1) % count,
+                Step::Redraw | Step::Stay => continue,
+                Step::Quit => return Ok(())
+            };
+            shown = Instant::now();
+        }
+    })();
+    drop(screen);
+    return result;
+}
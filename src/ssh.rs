@@ -0,0 +1,41 @@
+use crate::input::percent_decode;
+use std::process::{Command, Stdio};
+
+/// Schemes of files read over SSH.
+pub const SCHEMES: [&str; 3] = ["sftp://", "ssh://", "scp://"];
+
+// Quotes `s` for the POSIX shell that runs the remote command.
+fn shell_quote(s: &str) -> String {
+    return format!("'{}'", s.replace('\'', "'\\''"));
+}
+
+/// Contents of the file at `sftp://[user@]host[:port]/path`, read by running
+/// `cat` on the host through the ssh client, so that the keys, agent and
+/// configuration of the user apply. Paths starting with `/~/` are relative
+/// to the home directory.
+pub fn get(uri: &str) -> Result<Vec<u8>, String> {
+    let rest = SCHEMES.iter().find_map(|s| uri.strip_prefix(s)).unwrap_or(uri);
+    let (authority, path) = rest.split_once('/').ok_or(format!("Expected a path after the host: {}", uri))?;
+    let path = String::from_utf8(percent_decode(path)).map_err(|_| format!("The path is not UTF-8: {}", uri))?;
+    let remote_path = match path.strip_prefix("~/") {
+        Some(relative) => shell_quote(relative),
+        None => shell_quote(&format!("/{}", path))
+    };
+    let (destination, port) = match authority.rsplit_once(':') {
+        Some((destination, port)) if port.parse::<u16>().is_ok() => (destination, Some(port)),
+        _ => (authority, None)
+    };
+    let mut command = Command::new("ssh");
+    if let Some(port) = port {
+        command.args(["-p", port]);
+    }
+    let output = command.args(["--", destination, "cat", "--", &remote_path])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Unable to run ssh: {}", e))?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+        return Err(if message.is_empty() { format!("ssh exited with {}", output.status) } else { message });
+    }
+    return Ok(output.stdout);
+}
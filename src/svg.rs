@@ -0,0 +1,38 @@
+use image::DynamicImage;
+use image::RgbaImage;
+use resvg::tiny_skia;
+use resvg::usvg;
+
+/// Rasterizes an SVG document to a whole number of `chunk_width` x
+/// `chunk_height` chunks covering its intrinsic size, so that no pixels are
+/// dropped at the right and bottom edges.
+pub fn rasterize(data: &[u8], chunk_width: u32, chunk_height: u32) -> Result<DynamicImage, String> {
+    let mut options = usvg::Options::default();
+    options.fontdb_mut().load_system_fonts();
+    let tree = usvg::Tree::from_data(data, &options).map_err(|e| e.to_string())?;
+
+    let size = tree.size();
+    let width = (size.width() / chunk_width as f32).ceil().max(1.0) as u32 * chunk_width;
+    let height = (size.height() / chunk_height as f32).ceil().max(1.0) as u32 * chunk_height;
+    let mut pixmap = match tiny_skia::Pixmap::new(width, height) {
+        Some(p) => p,
+        None => return Err(format!("Unable to allocate a {}x{} pixmap", width, height))
+    };
+    let transform = tiny_skia::Transform::from_scale(width as f32 / size.width(), height as f32 / size.height());
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    // tiny-skia stores premultiplied alpha.
+    let mut data = pixmap.take();
+    for px in data.chunks_exact_mut(4) {
+        let a = px[3] as u32;
+        if a > 0 && a < 255 {
+            for c in px.iter_mut().take(3) {
+                *c = (*c as u32 * 255 / a) as u8;
+            }
+        }
+    }
+    return match RgbaImage::from_raw(width, height, data) {
+        Some(buf) => Ok(DynamicImage::ImageRgba8(buf)),
+        None => Err(String::from("Pixmap size does not match the image dimensions"))
+    }
+}
@@ -0,0 +1,70 @@
+use crate::perceptual::Distance;
+use crate::render::rgb_components;
+use ansi_term::Colour;
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Theme {
+    /// Gruvbox dark
+    Gruvbox,
+    /// Solarized base and accent colours
+    Solarized,
+    /// Nord polar night, snow storm, frost and aurora
+    Nord,
+    /// Dracula
+    Dracula,
+    /// Catppuccin mocha
+    Catppuccin,
+}
+
+const GRUVBOX: [u32; 16] = [
+    0x282828, 0xcc241d, 0x98971a, 0xd79921, 0x458588, 0xb16286, 0x689d6a, 0xa89984,
+    0x928374, 0xfb4934, 0xb8bb26, 0xfabd2f, 0x83a598, 0xd3869b, 0x8ec07c, 0xebdbb2,
+];
+
+const SOLARIZED: [u32; 16] = [
+    0x002b36, 0x073642, 0x586e75, 0x657b83, 0x839496, 0x93a1a1, 0xeee8d5, 0xfdf6e3,
+    0xb58900, 0xcb4b16, 0xdc322f, 0xd33682, 0x6c71c4, 0x268bd2, 0x2aa198, 0x859900,
+];
+
+const NORD: [u32; 16] = [
+    0x2e3440, 0x3b4252, 0x434c5e, 0x4c566a, 0xd8dee9, 0xe5e9f0, 0xeceff4, 0x8fbcbb,
+    0x88c0d0, 0x81a1c1, 0x5e81ac, 0xbf616a, 0xd08770, 0xebcb8b, 0xa3be8c, 0xb48ead,
+];
+
+const DRACULA: [u32; 11] = [
+    0x282a36, 0x44475a, 0xf8f8f2, 0x6272a4, 0x8be9fd, 0x50fa7b, 0xffb86c, 0xff79c6,
+    0xbd93f9, 0xff5555, 0xf1fa8c,
+];
+
+const CATPPUCCIN: [u32; 21] = [
+    0xf5e0dc, 0xf2cdcd, 0xf5c2e7, 0xcba6f7, 0xf38ba8, 0xeba0ac, 0xfab387, 0xf9e2af,
+    0xa6e3a1, 0x94e2d5, 0x89dceb, 0x74c7ec, 0x89b4fa, 0xb4befe, 0xcdd6f4, 0xbac2de,
+    0x6c7086, 0x313244, 0x1e1e2e, 0x181825, 0x11111b,
+];
+
+impl Theme {
+    fn colours(&self) -> &'static [u32] {
+        return match self {
+            Theme::Gruvbox => &GRUVBOX,
+            Theme::Solarized => &SOLARIZED,
+            Theme::Nord => &NORD,
+            Theme::Dracula => &DRACULA,
+            Theme::Catppuccin => &CATPPUCCIN,
+        }
+    }
+}
+
+fn unpack(hex: u32) -> (u8, u8, u8) {
+    return ((hex >> 16) as u8, (hex >> 8) as u8, hex as u8);
+}
+
+/// Snaps an RGB colour to the colour of `theme` closest by `distance`.
+pub fn snap(colour: Colour, theme: Theme, distance: Distance) -> Colour {
+    let rgb = rgb_components(&colour);
+    let (r, g, b) = theme.colours().iter()
+        .map(|c| unpack(*c))
+        .min_by(|a, b| distance.between(rgb, *a).total_cmp(&distance.between(rgb, *b)))
+        .unwrap_or(rgb);
+    return Colour::RGB(r, g, b);
+}
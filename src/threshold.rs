@@ -0,0 +1,100 @@
+use crate::Grid;
+use crate::palette;
+use crate::palette::{ColorDepth, Dither};
+use crate::render::luminance;
+use ansi_term::{Colour, Style};
+use std::fmt::Write;
+
+/// Whether each chunk of `grid` is white, its luminance from 0 to 1 at
+/// least `level`. Dithering spreads the error of each chunk to the chunks
+/// after it or adds the Bayer pattern over the whole range, so that grays
+/// become a pattern of black and white chunks.
+pub fn binarize(grid: &Grid<Colour>, level: f64, dither: Dither) -> Grid<bool> {
+    let mut values: Vec<f64> = grid.cells.iter().map(|c| luminance(c) / 255.0).collect();
+    if dither == Dither::Ordered {
+        for (i, v) in values.iter_mut().enumerate() {
+            *v += palette::bayer(i % grid.n_x, i / grid.n_x);
+        }
+    }
+    let mut cells = vec![false; values.len()];
+    for y in 0 .. grid.n_y {
+        for x in 0 .. grid.n_x {
+            let i = y * grid.n_x + x;
+            cells[i] = values[i] >= level;
+            if dither != Dither::FloydSteinberg {
+                continue;
+            }
+            let error = values[i] - if cells[i] { 1.0 } else { 0.0 };
+            let mut spread = |dx: isize, dy: usize, weight: f64| {
+                let nx = x as isize + dx;
+                if nx >= 0 && (nx as usize) < grid.n_x && y + dy < grid.n_y {
+                    values[(y + dy) * grid.n_x + nx as usize] += error * weight;
+                }
+            };
+            spread(1, 0, 7.0 / 16.0);
+            spread(-1, 1, 3.0 / 16.0);
+            spread(0, 1, 5.0 / 16.0);
+            spread(1, 1, 1.0 / 16.0);
+        }
+    }
+    return Grid { n_x: grid.n_x, n_y: grid.n_y, cells };
+}
+
+/// Two chunks of `cells` per terminal cell drawn with half blocks, white on
+/// black, or in the terminal foreground on its background without colours.
+pub fn lines(cells: &Grid<bool>, depth: ColorDepth) -> Vec<String> {
+    let style = match depth {
+        ColorDepth::NoColor => Style::new(),
+        d => palette::on(Colour::RGB(255, 255, 255), Colour::RGB(0, 0, 0), d)
+    };
+    return (0 .. cells.n_y).step_by(2).map(|y| {
+        let lower = (y + 1 < cells.n_y).then(|| cells.row(y + 1));
+        let glyphs: String = cells.row(y).iter().enumerate().map(|(x, upper)| {
+            match (*upper, lower.is_some_and(|l| l[x])) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' '
+            }
+        }).collect();
+        let mut line = String::new();
+        let _ = write!(line, "{}{}{}", style.prefix(), glyphs, style.suffix());
+        line
+    }).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(v: u8, n_x: usize, n_y: usize) -> Grid<Colour> {
+        return Grid { n_x, n_y, cells: vec![Colour::RGB(v, v, v); n_x * n_y] };
+    }
+
+    #[test]
+    fn level_splits_luminance() {
+        assert!(binarize(&gray(128, 2, 2), 0.5, Dither::None).cells.iter().all(|c| *c));
+        assert!(binarize(&gray(127, 2, 2), 0.5, Dither::None).cells.iter().all(|c| !*c));
+    }
+
+    #[test]
+    fn ordered_dither_tiles_the_pattern() {
+        // A quarter gray switches on 4 of the 16 chunks of every tile.
+        let cells = binarize(&gray(64, 8, 8), 0.5, Dither::Ordered).cells;
+        assert_eq!(cells.iter().filter(|c| **c).count(), 16);
+        assert_eq!(cells[.. 4], cells[32 .. 36]);
+    }
+
+    #[test]
+    fn error_diffusion_keeps_the_mean() {
+        let cells = binarize(&gray(64, 16, 16), 0.5, Dither::FloydSteinberg).cells;
+        let white = cells.iter().filter(|c| **c).count();
+        assert!((white as f64 / cells.len() as f64 - 0.25).abs() < 0.03, "{}", white);
+    }
+
+    #[test]
+    fn half_blocks_pair_rows() {
+        let cells = Grid { n_x: 4, n_y: 3, cells: vec![true, true, false, false, true, false, true, false, false, true, true, false] };
+        assert_eq!(lines(&cells, ColorDepth::NoColor), vec!["█▀▄ ", " ▀▀ "]);
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Parse,
+    Fetch,
+    Decode,
+    Aggregate,
+    Render,
+    Print,
+}
+
+const STAGES: [Stage; 6] = [Stage::Parse, Stage::Fetch, Stage::Decode, Stage::Aggregate, Stage::Render, Stage::Print];
+
+impl Stage {
+    fn name(&self) -> &'static str {
+        return match self {
+            Stage::Parse => "parse",
+            Stage::Fetch => "fetch",
+            Stage::Decode => "decode",
+            Stage::Aggregate => "aggregate",
+            Stage::Render => "render",
+            Stage::Print => "print",
+        }
+    }
+}
+
+/// Time spent in each stage, summed over all images. Inputs fetched
+/// concurrently can add up to more than the wall time.
+struct Timings {
+    totals: [Duration; STAGES.len()],
+}
+
+static TIMINGS: Mutex<Timings> = Mutex::new(Timings { totals: [Duration::ZERO; STAGES.len()] });
+
+pub fn record(stage: Stage, duration: Duration) {
+    if let Ok(mut timings) = TIMINGS.lock() {
+        timings.totals[stage as usize] += duration;
+    }
+}
+
+/// Runs `f` and adds its duration to `stage`.
+pub fn measure<T, F: FnOnce() -> T>(stage: Stage, f: F) -> T {
+    let start = Instant::now();
+    let result = f();
+    record(stage, start.elapsed());
+    return result;
+}
+
+/// Time spent in `stage` so far.
+pub fn total(stage: Stage) -> Duration {
+    return TIMINGS.lock().map(|t| t.totals[stage as usize]).unwrap_or_default();
+}
+
+/// Per stage breakdown followed by the wall time since `start`.
+pub fn report(start: Instant) -> Vec<String> {
+    let totals = TIMINGS.lock().map(|t| t.totals).unwrap_or_default();
+    let mut lines = vec![String::from("timing:")];
+    for stage in STAGES {
+        lines.push(format!("  {:<10}{:>10.3} ms", stage.name(), totals[stage as usize].as_secs_f64() * 1000.0));
+    }
+    lines.push(format!("  {:<10}{:>10.3} ms", "total", start.elapsed().as_secs_f64() * 1000.0));
+    return lines;
+}
@@ -0,0 +1,39 @@
+use std::process::{Command, Stdio};
+
+/// Whether tcolr runs inside tmux, which swallows escape sequences it does
+/// not know unless they are wrapped by `wrap`.
+pub fn inside() -> bool {
+    return std::env::var_os("TMUX").is_some_and(|v| !v.is_empty());
+}
+
+/// Whether the `allow-passthrough` option of the current pane lets wrapped
+/// sequences through. It defaults to off since tmux 3.3, before which there
+/// was no such option and passthrough was always allowed.
+pub fn allows_passthrough() -> bool {
+    let output = Command::new("tmux")
+        .args(["show-options", "-Apv", "allow-passthrough"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    return match output {
+        Ok(o) if o.status.success() => matches!(String::from_utf8_lossy(&o.stdout).trim(), "on" | "all"),
+        Ok(_) => true,
+        Err(_) => false
+    };
+}
+
+/// `sequence` in the DCS envelope tmux passes on to the terminal unchanged,
+/// with the escapes inside doubled.
+pub fn wrap(sequence: &str) -> String {
+    return format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_are_doubled() {
+        assert_eq!(wrap("\x1bP0;1;0q#0\x1b\\"), "\x1bPtmux;\x1b\x1bP0;1;0q#0\x1b\x1b\\\x1b\\");
+    }
+}
@@ -0,0 +1,52 @@
+use crate::aggregate::{linear_to_srgb, srgb_unit_to_linear};
+use clap::ValueEnum;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ToneMap {
+    /// Clip everything brighter than white
+    Clamp,
+    /// Compress highlights with x / (1 + x)
+    Reinhard,
+    /// Filmic curve approximating the ACES reference rendering transform
+    Aces,
+}
+
+impl ToneMap {
+    /// Maps linear light, possibly brighter than 1, into `[0, 1]`.
+    fn map(&self, c: f64) -> f64 {
+        let c = c.max(0.0);
+        return match self {
+            ToneMap::Clamp => c.min(1.0),
+            ToneMap::Reinhard => c / (1.0 + c),
+            // Krzysztof Narkowicz's fit of the ACES curve.
+            ToneMap::Aces => ((c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14)).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Whether the channels of `image` have more than 8 bits.
+pub fn is_high_depth(image: &DynamicImage) -> bool {
+    return matches!(image,
+        DynamicImage::ImageLuma16(_) | DynamicImage::ImageLumaA16(_) |
+        DynamicImage::ImageRgb16(_) | DynamicImage::ImageRgba16(_) |
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_));
+}
+
+/// 8 bit sRGB copy of an image with 16 bit or floating point channels,
+/// tone mapped in linear light. Floating point channels, as decoded from
+/// OpenEXR and Radiance HDR files, already hold linear light, while 16 bit
+/// channels are sRGB encoded like 8 bit ones.
+pub fn apply(image: &DynamicImage, tonemap: ToneMap) -> DynamicImage {
+    let linear = matches!(image, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_));
+    let source = image.to_rgba32f();
+    let mapped = RgbaImage::from_fn(source.width(), source.height(), |x, y| {
+        let p = source.get_pixel(x, y);
+        let channel = |c: f32| {
+            let c = c as f64;
+            linear_to_srgb(tonemap.map(if linear { c } else { srgb_unit_to_linear(c) }))
+        };
+        Rgba([channel(p[0]), channel(p[1]), channel(p[2]), (p[3].clamp(0.0, 1.0) * 255.0).round() as u8])
+    });
+    return DynamicImage::ImageRgba8(mapped);
+}
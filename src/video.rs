@@ -0,0 +1,69 @@
+use ffmpeg_next as ffmpeg;
+use ffmpeg::format::Pixel;
+use ffmpeg::media::Type;
+use ffmpeg::software::scaling::{context::Context, flag::Flags};
+use ffmpeg::util::frame::video::Video;
+use image::DynamicImage;
+use image::RgbImage;
+use std::time::Duration;
+
+fn to_image(frame: &Video) -> DynamicImage {
+    let (width, height) = (frame.width(), frame.height());
+    let stride = frame.stride(0);
+    let data = frame.data(0);
+    let mut buf = RgbImage::new(width, height);
+    for y in 0 .. height as usize {
+        let row = &data[y * stride .. y * stride + width as usize * 3];
+        for (x, px) in row.chunks_exact(3).enumerate() {
+            buf.put_pixel(x as u32, y as u32, image::Rgb([px[0], px[1], px[2]]));
+        }
+    }
+    return DynamicImage::ImageRgb8(buf);
+}
+
+/// Decodes the first frame of the video at `path` shown at or after
+/// `timestamp`, or the very first frame without a timestamp.
+pub fn frame_at(path: &str, timestamp: Option<Duration>) -> Result<DynamicImage, ffmpeg::Error> {
+    ffmpeg::init()?;
+    let mut ictx = ffmpeg::format::input(&path)?;
+    let input = ictx.streams().best(Type::Video).ok_or(ffmpeg::Error::StreamNotFound)?;
+    let stream_index = input.index();
+    let time_base = input.time_base();
+    let context = ffmpeg::codec::context::Context::from_parameters(input.parameters())?;
+    let mut decoder = context.decoder().video()?;
+
+    let target = timestamp.map(|t| t.as_secs_f64()).unwrap_or(0.0);
+    if target > 0.0 {
+        // Seeks to the closest key frame before the timestamp.
+        let position = (target * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+        ictx.seek(position, ..position)?;
+    }
+
+    let mut scaler = Context::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        Pixel::RGB24,
+        decoder.width(),
+        decoder.height(),
+        Flags::BILINEAR,
+    )?;
+
+    let seconds = |pts: i64| pts as f64 * time_base.numerator() as f64 / time_base.denominator() as f64;
+    let mut decoded = Video::empty();
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+        decoder.send_packet(&packet)?;
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            if decoded.timestamp().map(seconds).unwrap_or(target) < target {
+                continue;
+            }
+            let mut rgb = Video::empty();
+            scaler.run(&decoded, &mut rgb)?;
+            return Ok(to_image(&rgb));
+        }
+    }
+    return Err(ffmpeg::Error::Eof);
+}
@@ -0,0 +1,114 @@
+use crate::screen::Screen;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::io::{self, Write};
+
+// Factor by which each key press zooms, and the furthest zoom allowed.
+const ZOOM_STEP: f64 = 1.5;
+const MAX_ZOOM: f64 = 64.0;
+
+// Fraction of the visible region moved by each key press.
+const PAN_STEP: f64 = 0.25;
+
+/// Part of the image shown by the viewer, as a zoom factor and the centre of
+/// the visible region relative to the image size.
+#[derive(Clone, Copy, Debug)]
+pub struct View {
+    pub zoom: f64,
+    pub centre: (f64, f64),
+    pub grayscale: bool,
+}
+
+impl View {
+    fn new() -> View {
+        return View { zoom: 1.0, centre: (0.5, 0.5), grayscale: false };
+    }
+
+    /// Pixel region `(x, y, width, height)` of a `width` x `height` image
+    /// that is visible.
+    pub fn region(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let extent = |size: u32, centre: f64| {
+            let visible = (size as f64 / self.zoom).round().clamp(1.0, size as f64);
+            let start = (centre * size as f64 - visible / 2.0).round().clamp(0.0, size as f64 - visible);
+            (start as u32, visible as u32)
+        };
+        let (x, w) = extent(width, self.centre.0);
+        let (y, h) = extent(height, self.centre.1);
+        return (x, y, w, h);
+    }
+
+    fn pan(&mut self, dx: f64, dy: f64) {
+        // Keeps the visible region inside the image.
+        let half = 0.5 / self.zoom;
+        self.centre.0 = (self.centre.0 + dx * PAN_STEP / self.zoom).clamp(half, 1.0 - half);
+        self.centre.1 = (self.centre.1 + dy * PAN_STEP / self.zoom).clamp(half, 1.0 - half);
+    }
+
+    fn zoom_by(&mut self, factor: f64) {
+        self.zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+        self.pan(0.0, 0.0);
+    }
+
+    fn status(&self) -> String {
+        return format!("zoom {:.1}x  arrows pan  +/- zoom  g grayscale  0 reset  q quit", self.zoom);
+    }
+}
+
+enum Action {
+    Update(View),
+    Quit,
+    Stay,
+}
+
+fn action(key: KeyCode, modifiers: KeyModifiers, view: View) -> Action {
+    let mut next = view;
+    match key {
+        KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Action::Quit,
+        KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+        KeyCode::Left | KeyCode::Char('h') => next.pan(-1.0, 0.0),
+        KeyCode::Right | KeyCode::Char('l') => next.pan(1.0, 0.0),
+        KeyCode::Up | KeyCode::Char('k') => next.pan(0.0, -1.0),
+        KeyCode::Down | KeyCode::Char('j') => next.pan(0.0, 1.0),
+        KeyCode::Char('+') | KeyCode::Char('=') => next.zoom_by(ZOOM_STEP),
+        KeyCode::Char('-') => next.zoom_by(1.0 / ZOOM_STEP),
+        KeyCode::Char('g') => next.grayscale = !next.grayscale,
+        KeyCode::Char('0') => next = View::new(),
+        _ => return Action::Stay
+    }
+    return Action::Update(next);
+}
+
+/// Shows the lines rendered by `show` for the current view with a status line
+/// below them. The arrow keys pan, `+`/`-` zoom, `g` toggles grayscale, `0`
+/// resets the view and `q`/Esc quits. The view is rendered again when the
+/// terminal is resized.
+pub fn run<F: FnMut(&View) -> Vec<String>>(mut show: F) -> io::Result<()> {
+    let screen = Screen::enter()?;
+    let result = (|| {
+        let mut view = View::new();
+        loop {
+            let mut out = io::stdout().lock();
+            write!(out, "\x1b[2J\x1b[H")?;
+            // Raw mode does not translate `\n` into a carriage return.
+            for line in show(&view) {
+                write!(out, "{}\r\n", line)?;
+            }
+            write!(out, "{}", view.status())?;
+            out.flush()?;
+            drop(out);
+
+            view = loop {
+                match event::read()? {
+                    Event::Key(k) if k.kind == KeyEventKind::Press => match action(k.code, k.modifiers, view) {
+                        Action::Update(v) => break v,
+                        Action::Quit => return Ok(()),
+                        Action::Stay => continue
+                    },
+                    Event::Resize(_, _) => break view,
+                    _ => continue
+                }
+            };
+        }
+    })();
+    drop(screen);
+    return result;
+}
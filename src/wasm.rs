@@ -0,0 +1,19 @@
+use crate::{finish, render_image, Args};
+use clap::Parser;
+use image::{DynamicImage, RgbaImage};
+use wasm_bindgen::prelude::*;
+
+/// Renders `width` x `height` RGBA pixels, row by row, the way the command
+/// line tool renders an image with the whitespace separated `args`, such as
+/// `--cols 80 --mode half-block`. Colours are always emitted, and the result
+/// is ANSI text for a terminal emulator like xterm.js, or HTML with
+/// `--output html`.
+#[wasm_bindgen]
+pub fn render_rgba(rgba: Vec<u8>, width: u32, height: u32, args: &str) -> Result<String, JsError> {
+    let image = RgbaImage::from_raw(width, height, rgba)
+        .ok_or_else(|| JsError::new(&format!("Expected {} bytes for {}x{} RGBA pixels", width as u64 * height as u64 * 4, width, height)))?;
+    let argv = ["tcolr", "--color=always"].into_iter().chain(args.split_whitespace());
+    let args = Args::try_parse_from(argv).map_err(|e| JsError::new(&e.to_string()))?.with_preview_defaults();
+    let lines = render_image(&DynamicImage::ImageRgba8(image), &args).map_err(|e| JsError::new(&e.to_string()))?;
+    return Ok(finish(lines, &args).join("\n"));
+}
@@ -0,0 +1,73 @@
+use notify::{RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+// Editors often write a file in several steps; events arriving within this
+// window after the first one trigger a single redraw.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+fn parent(path: &Path) -> PathBuf {
+    return match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from(".")
+    }
+}
+
+enum Trigger {
+    File(notify::Result<notify::Event>),
+    Resize,
+}
+
+#[cfg(unix)]
+fn notify_on_resize(tx: mpsc::Sender<Trigger>) -> std::io::Result<()> {
+    let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGWINCH])?;
+    std::thread::spawn(move || {
+        for _ in signals.forever() {
+            if tx.send(Trigger::Resize).is_err() {
+                break;
+            }
+        }
+    });
+    return Ok(());
+}
+
+#[cfg(not(unix))]
+fn notify_on_resize(_tx: mpsc::Sender<Trigger>) -> std::io::Result<()> {
+    return Ok(());
+}
+
+/// Calls `redraw` once and then every time one of `paths` changes or the
+/// terminal is resized. Parent directories are watched so that files
+/// replaced by editors are picked up.
+pub fn run<F: FnMut()>(paths: &[PathBuf], mut redraw: F) -> notify::Result<()> {
+    let watched: HashSet<PathBuf> = paths.iter()
+        .map(|p| p.canonicalize().unwrap_or_else(|_| p.clone()))
+        .collect();
+    let (tx, rx) = mpsc::channel();
+    let file_tx = tx.clone();
+    let mut watcher = notify::recommended_watcher(move |e| {
+        let _ = file_tx.send(Trigger::File(e));
+    })?;
+    for dir in watched.iter().map(|p| parent(p)).collect::<HashSet<_>>() {
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+    }
+    notify_on_resize(tx)?;
+
+    let is_relevant = |trigger: &Trigger| match trigger {
+        Trigger::File(Ok(e)) => !e.kind.is_access() && e.paths.iter().any(|p| watched.contains(p)),
+        Trigger::File(Err(_)) => false,
+        Trigger::Resize => true
+    };
+
+    redraw();
+    while let Ok(trigger) = rx.recv() {
+        if !is_relevant(&trigger) {
+            continue;
+        }
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+        redraw();
+    }
+    return Ok(());
+}
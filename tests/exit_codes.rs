@@ -0,0 +1,71 @@
+// Runs tcolr on inputs that fail in each of the ways listed under "Exit
+// codes" in --help and checks the code it exits with.
+
+#![allow(clippy::needless_return)]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn exit_code(args: &[&str]) -> Option<i32> {
+    let output = Command::new(env!("CARGO_BIN_EXE_tcolr"))
+        .current_dir(PathBuf::from(env!("CARGO_MANIFEST_DIR")))
+        .arg("--deterministic")
+        .args(args)
+        .output()
+        .expect("tcolr runs");
+    return output.status.code();
+}
+
+#[test]
+fn success_is_0() {
+    assert_eq!(exit_code(&["tests/fixtures/disc.png"]), Some(0));
+}
+
+#[test]
+fn identical_images_pass_any_threshold() {
+    assert_eq!(exit_code(&["compare", "tests/fixtures/disc.png", "tests/fixtures/disc.png", "--metric", "psnr", "--threshold", "1000"]), Some(0));
+}
+
+#[test]
+fn dissimilar_images_are_mismatches() {
+    assert_eq!(exit_code(&["compare", "tests/fixtures/disc.png", "tests/fixtures/gradient.png", "--metric", "psnr", "--threshold", "1000"]), Some(1));
+    assert_eq!(exit_code(&["compare", "tests/fixtures/disc.png", "tests/fixtures/gradient.png", "--metric", "deltaE", "--threshold", "0.1"]), Some(1));
+}
+
+#[test]
+fn zero_chunk_sizes_are_usage_errors() {
+    assert_eq!(exit_code(&["tests/fixtures/disc.png", "-x", "0"]), Some(2));
+    assert_eq!(exit_code(&["tests/fixtures/disc.png", "-y", "0"]), Some(2));
+}
+
+#[test]
+fn zero_intervals_are_usage_errors() {
+    assert_eq!(exit_code(&["tests/fixtures", "--interval", "0"]), Some(2));
+}
+
+#[test]
+fn empty_crops_are_usage_errors() {
+    assert_eq!(exit_code(&["tests/fixtures/disc.png", "--crop", "0,0,0,10"]), Some(2));
+    assert_eq!(exit_code(&["tests/fixtures/disc.png", "--crop", "40,40,5,5"]), Some(2));
+    assert_eq!(exit_code(&["tests/fixtures/disc.png", "--crop", "0,0,1%,1%"]), Some(2));
+}
+
+#[test]
+fn unknown_flags_are_usage_errors() {
+    assert_eq!(exit_code(&["tests/fixtures/disc.png", "--no-such-flag"]), Some(2));
+}
+
+#[test]
+fn missing_inputs_are_fetch_errors() {
+    assert_eq!(exit_code(&["tests/fixtures/missing.png"]), Some(3));
+}
+
+#[test]
+fn unknown_formats_are_decode_errors() {
+    assert_eq!(exit_code(&["tests/fixtures/warm.cube"]), Some(4));
+}
+
+#[test]
+fn unwritable_outputs_are_render_errors() {
+    assert_eq!(exit_code(&["tests/fixtures/disc.png", "--output-file", "tests/fixtures/missing/out.ans"]), Some(5));
+}
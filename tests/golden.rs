@@ -0,0 +1,90 @@
+// Renders the images in tests/fixtures with --deterministic and compares the
+// output byte for byte with the .ans files in tests/golden. After an intended
+// change of the output, rewrite the golden files with
+//
+//     TCOLR_BLESS=1 cargo test --test golden
+
+#![allow(clippy::needless_return)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const CASES: &[(&str, &str, &[&str])] = &[
+    ("gradient-runs", "gradient.png", &["-x", "4", "-y", "8"]),
+    ("gradient-runs-bg", "gradient.png", &["-x", "4", "-y", "8", "--paint", "bg"]),
+    ("gradient-half-block", "gradient.png", &["--mode", "half-block", "-x", "2", "-y", "2"]),
+    ("gradient-braille", "gradient.png", &["--mode", "braille", "-x", "1", "-y", "1"]),
+    ("gradient-fit", "gradient.png", &["--fit"]),
+    ("gradient-256-dither", "gradient.png", &["-x", "4", "-y", "8", "--colors", "256", "--dither"]),
+    ("gradient-256-ciede2000", "gradient.png", &["-x", "4", "-y", "8", "--colors", "256", "--color-distance", "ciede2000"]),
+    ("gradient-theme-oklab", "gradient.png", &["-x", "4", "-y", "8", "--theme", "nord", "--color-distance", "oklab"]),
+    ("gradient-lut", "gradient.png", &["-x", "4", "-y", "8", "--lut", "tests/fixtures/warm.cube"]),
+    ("gradient-posterize", "gradient.png", &["-x", "4", "-y", "8", "--posterize", "6"]),
+    ("disc-max-colors", "disc.png", &["--mode", "half-block", "-x", "2", "-y", "2", "--max-colors", "3"]),
+    ("gradient-16", "gradient.png", &["-x", "4", "-y", "8", "--colors", "16"]),
+    ("gradient-16-ordered", "gradient.png", &["-x", "4", "-y", "8", "--colors", "16", "--dither", "ordered"]),
+    ("gradient-shades-16", "gradient.png", &["--mode", "shades", "-x", "4", "-y", "8", "--colors", "16"]),
+    ("gradient-shades-256", "gradient.png", &["--mode", "shades", "-x", "4", "-y", "8", "--colors", "256"]),
+    ("disc-oklab", "disc.png", &["-x", "4", "-y", "4", "--average-space", "oklab"]),
+    ("disc-linear", "disc.png", &["-x", "4", "-y", "4", "--linear"]),
+    ("disc-half-block", "disc.png", &["--mode", "half-block", "-x", "2", "-y", "2"]),
+    ("disc-ascii", "disc.png", &["--mode", "ascii", "-x", "2", "-y", "4"]),
+    ("disc-auto-contrast", "disc.png", &["-x", "4", "-y", "4", "--auto-contrast"]),
+    ("disc-equalize", "disc.png", &["-x", "4", "-y", "4", "--equalize"]),
+    ("disc-kernel-emboss", "disc.png", &["--mode", "ascii", "-x", "2", "-y", "4", "--kernel", "-2,-1,0,-1,1,1,0,1,2"]),
+    ("disc-sharpen", "disc.png", &["--mode", "ascii", "-x", "4", "-y", "4", "--sharpen", "2"]),
+    ("disc-sharpen-after", "disc.png", &["-x", "2", "-y", "4", "--sharpen", "1", "--sharpen-stage", "after"]),
+    ("disc-edges", "disc.png", &["--mode", "edges", "-x", "1", "-y", "1"]),
+    ("disc-edge-lines", "disc.png", &["--mode", "edges", "-x", "1", "-y", "1", "--edge-glyphs", "lines", "--edge-color"]),
+    ("disc-blocks", "disc.png", &["--mode", "blocks", "-x", "1", "-y", "1"]),
+    ("disc-blocks-sextants", "disc.png", &["--mode", "blocks", "-x", "1", "-y", "1", "--glyphs", "sextants"]),
+    ("gradient-threshold", "gradient.png", &["--mode", "threshold", "-x", "2", "-y", "2", "--level", "0.4"]),
+    ("gradient-threshold-dither", "gradient.png", &["--mode", "threshold", "-x", "2", "-y", "2", "--dither"]),
+    ("disc-threshold-ordered", "disc.png", &["--mode", "threshold", "-x", "1", "-y", "1", "--dither", "ordered"]),
+    ("disc-border", "disc.png", &["-x", "2", "-y", "4", "--border", "rounded", "--border-title", "disc"]),
+    ("montage", "gradient.png", &["tests/fixtures/disc.png", "--grid-columns", "2"]),
+];
+
+fn root() -> PathBuf {
+    return PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+}
+
+fn render(fixture: &str, args: &[&str]) -> Vec<u8> {
+    let output = Command::new(env!("CARGO_BIN_EXE_tcolr"))
+        .current_dir(root())
+        .arg("--deterministic")
+        .arg(Path::new("tests/fixtures").join(fixture))
+        .args(args)
+        .env_remove("COLORTERM")
+        .env_remove("TERM")
+        .env_remove("NO_COLOR")
+        .output()
+        .expect("tcolr runs");
+    assert!(output.status.success(), "tcolr failed: {}", String::from_utf8_lossy(&output.stderr));
+    return output.stdout;
+}
+
+#[test]
+fn matches_golden_files() {
+    let bless = std::env::var_os("TCOLR_BLESS").is_some();
+    let mut mismatched = Vec::new();
+    for (name, fixture, args) in CASES {
+        let rendered = render(fixture, args);
+        let golden = root().join("tests/golden").join(format!("{}.ans", name));
+        if bless {
+            std::fs::write(&golden, &rendered).expect("golden file is written");
+            continue;
+        }
+        match std::fs::read(&golden) {
+            Ok(expected) if expected == rendered => {},
+            _ => mismatched.push(*name)
+        }
+    }
+    assert!(mismatched.is_empty(), "output differs from the golden files of {:?}, see tests/golden.rs to update them", mismatched);
+}
+
+#[test]
+fn renders_the_same_every_time() {
+    let (fixture, args) = (CASES[0].1, CASES[0].2);
+    assert_eq!(render(fixture, args), render(fixture, args));
+}